@@ -37,10 +37,12 @@ for subdir in "$notebooks_dir"/*; do
                 fi
 
                 # Add provingTime and memoryUsage fields to the notebook object
+                # riscZero also records verifyTime and proofSize
                 subdir_object=$(jq -n \
                     --arg name "$notebook_name" \
                     --argjson obj "$subdir_object" \
-                    '$obj + {($name): {"provingTime": [], "memoryUsage": []}}')
+                    '$obj + {($name): ({"provingTime": [], "memoryUsage": []}
+                        + if $name == "riscZero" then {"verifyTime": [], "proofSize": []} else {} end)}')
             fi
         done
 
@@ -0,0 +1,15 @@
+use std::process::Command;
+
+fn main() {
+    // Records the compiler this binary is built with, for the fingerprint.
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let output = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .expect("failed to run rustc --version");
+    println!(
+        "cargo:rustc-env=RUSTC_VERSION={}",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
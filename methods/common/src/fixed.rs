@@ -0,0 +1,213 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fixed-point counterparts of the linear regression and tree ensemble
+//! models. The smartcore guests compute in `f64`, which the zkVM emulates in
+//! software; these models compute the same predictions with integer
+//! arithmetic only, at a configurable scale, to tell the cost of the float
+//! emulation apart from that of the model itself.
+
+use serde::{Deserialize, Serialize};
+
+/// A linear regression or tree ensemble reduced to its parameters, in the
+/// number type `T`. The host extracts it in `f64` from the smartcore model
+/// and quantizes it to `i64` for the guest, see [FixedModel].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PlainModel<T> {
+    Linear {
+        coefficients: Vec<T>,
+        intercept: T,
+    },
+    /// Decision trees whose predictions are combined by `aggregate`. Each
+    /// tree is a list of nodes rooted at the first one.
+    Forest {
+        trees: Vec<Vec<Node<T>>>,
+        aggregate: Aggregate<T>,
+    },
+}
+
+/// How the predictions of the trees of a forest are combined.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Aggregate<T> {
+    /// The mean of the values of the leaves, as a regressor does.
+    Mean,
+    /// The class most trees vote for, as a classifier does. Ties go to the
+    /// class listed first.
+    Vote { classes: Vec<T> },
+}
+
+/// A node of a decision tree. Rows go to `split.true_child` when their
+/// feature is at most the threshold, and end at the first node without a
+/// split.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Node<T> {
+    pub split: Option<Split<T>>,
+    /// The value of the leaf, or for a [Aggregate::Vote] forest the index of
+    /// its class.
+    pub output: T,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Split<T> {
+    pub feature: u32,
+    pub threshold: T,
+    pub true_child: u32,
+    pub false_child: u32,
+}
+
+/// A [PlainModel] in fixed point: every value `v` is stored as the integer
+/// `v * 2^scale`, rounded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FixedModel {
+    pub scale: u32,
+    pub model: PlainModel<i64>,
+}
+
+/// Input rows in fixed point at the scale of the model they are given to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FixedMatrix {
+    pub ncols: u32,
+    /// The values, row by row.
+    pub values: Vec<i64>,
+}
+
+impl FixedMatrix {
+    pub fn rows(&self) -> impl Iterator<Item = &[i64]> {
+        self.values.chunks(self.ncols.max(1) as usize)
+    }
+}
+
+impl FixedModel {
+    /// Predicts every row of `x`, in fixed point at the model's scale, or
+    /// returns `None` when a prediction overflows `i64`.
+    pub fn predict(&self, x: &FixedMatrix) -> Option<Vec<i64>> {
+        x.rows()
+            .map(|row| self.model.predict(row, self.scale))
+            .collect()
+    }
+}
+
+/// The arithmetic a [PlainModel] predicts with: `f64` on the host, and `i64`
+/// in fixed point in the guest. Every operation returns `None` rather than
+/// overflowing.
+pub trait Number: Copy + PartialOrd {
+    const ZERO: Self;
+    /// The dot product of `a` and `b`, whose values have `scale` fractional
+    /// bits when in fixed point.
+    fn dot(a: &[Self], b: &[Self], scale: u32) -> Option<Self>;
+    fn plus(self, other: Self) -> Option<Self>;
+    /// Divides by a count of values.
+    fn div_count(self, count: usize) -> Option<Self>;
+    /// Reads a leaf of an [Aggregate::Vote] forest as a class index.
+    fn class_index(self) -> Option<usize>;
+}
+
+impl Number for f64 {
+    const ZERO: f64 = 0.0;
+
+    fn dot(a: &[f64], b: &[f64], _scale: u32) -> Option<f64> {
+        Some(a.iter().zip(b).map(|(a, b)| a * b).sum())
+    }
+
+    fn plus(self, other: f64) -> Option<f64> {
+        Some(self + other)
+    }
+
+    fn div_count(self, count: usize) -> Option<f64> {
+        Some(self / count as f64)
+    }
+
+    fn class_index(self) -> Option<usize> {
+        Some(self as usize)
+    }
+}
+
+/// Products are computed in `i128` and scaled back down by shifting, so they
+/// are rounded towards negative infinity.
+impl Number for i64 {
+    const ZERO: i64 = 0;
+
+    fn dot(a: &[i64], b: &[i64], scale: u32) -> Option<i64> {
+        let sum = a.iter().zip(b).try_fold(0i128, |sum, (&a, &b)| {
+            sum.checked_add(a as i128 * b as i128)
+        })?;
+        i64::try_from(sum >> scale).ok()
+    }
+
+    fn plus(self, other: i64) -> Option<i64> {
+        self.checked_add(other)
+    }
+
+    fn div_count(self, count: usize) -> Option<i64> {
+        self.checked_div(i64::try_from(count).ok()?)
+    }
+
+    fn class_index(self) -> Option<usize> {
+        usize::try_from(self).ok()
+    }
+}
+
+impl<T: Number> PlainModel<T> {
+    /// Predicts `row`, whose values have `scale` fractional bits when `T` is
+    /// in fixed point. Returns `None` when the arithmetic overflows or a leaf
+    /// names a class the forest does not have.
+    pub fn predict(&self, row: &[T], scale: u32) -> Option<T> {
+        match self {
+            PlainModel::Linear {
+                coefficients,
+                intercept,
+            } => T::dot(coefficients, row, scale)?.plus(*intercept),
+            PlainModel::Forest { trees, aggregate } => {
+                let mut leaves = trees.iter().map(|tree| leaf(tree, row));
+                match aggregate {
+                    Aggregate::Mean => leaves
+                        .try_fold(T::ZERO, |sum, leaf| sum.plus(leaf))?
+                        .div_count(trees.len()),
+                    Aggregate::Vote { classes } => {
+                        let mut votes = vec![0usize; classes.len()];
+                        for class in leaves {
+                            *votes.get_mut(class.class_index()?)? += 1;
+                        }
+                        // The first class with the most votes wins.
+                        let winner =
+                            (0..votes.len()).fold(
+                                0,
+                                |best, i| {
+                                    if votes[i] > votes[best] {
+                                        i
+                                    } else {
+                                        best
+                                    }
+                                },
+                            );
+                        classes.get(winner).copied()
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Walks `tree` down to the leaf `row` falls in and returns its output.
+fn leaf<T: Number>(tree: &[Node<T>], row: &[T]) -> T {
+    let mut node = &tree[0];
+    while let Some(split) = &node.split {
+        node = if row[split.feature as usize] <= split.threshold {
+            &tree[split.true_child as usize]
+        } else {
+            &tree[split.false_child as usize]
+        };
+    }
+    node.output
+}
@@ -0,0 +1,327 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Types and helpers shared by the guests and the host.
+
+pub mod fixed;
+
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
+use serde::{Deserialize, Serialize};
+use smartcore::{
+    ensemble::{
+        random_forest_classifier::RandomForestClassifier,
+        random_forest_regressor::RandomForestRegressor,
+    },
+    linalg::basic::matrix::DenseMatrix,
+    linear::linear_regression::LinearRegression,
+    svm::svc::SVC,
+};
+
+/// What every guest commits to the journal: its predictions, bound to the
+/// model and input data they were computed from. Each of those is either
+/// committed verbatim or only as its [digest], depending on the
+/// [Visibility] the host asked for.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Prediction<M, I, T> {
+    pub model: Disclosed<M>,
+    pub input: Disclosed<I>,
+    pub y_hat: T,
+}
+
+/// Which parts of a run the guest reveals in the journal. The host writes it
+/// to the guest ahead of the model and input data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Visibility {
+    pub model: Disclosure,
+    pub input: Disclosure,
+}
+
+/// How a part of a run is committed to the journal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Disclosure {
+    /// Committed verbatim.
+    Public,
+    /// Committed only as its [digest], which binds the predictions to it
+    /// without revealing it.
+    #[default]
+    Private,
+}
+
+impl Disclosure {
+    pub fn disclose<T: Serialize>(self, value: &T) -> Disclosed<&T> {
+        match self {
+            Disclosure::Public => Disclosed::Public(value),
+            Disclosure::Private => Disclosed::Private(digest(value)),
+        }
+    }
+}
+
+/// A part of a run as committed to the journal, see [Disclosure].
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum Disclosed<T> {
+    Public(T),
+    Private(Digest),
+}
+
+impl<T: Serialize> Disclosed<T> {
+    /// Returns the digest of the part, computing it when it was committed
+    /// verbatim.
+    pub fn digest(&self) -> Digest {
+        match self {
+            Disclosed::Public(value) => digest(value),
+            Disclosed::Private(digest) => *digest,
+        }
+    }
+
+    pub fn disclosure(&self) -> Disclosure {
+        match self {
+            Disclosed::Public(_) => Disclosure::Public,
+            Disclosed::Private(_) => Disclosure::Private,
+        }
+    }
+}
+
+/// Returns the SHA-256 of `value` in risc0 serde encoding, which is how the
+/// host writes it to the guest. In the guest this runs on the zkVM's SHA
+/// accelerator.
+pub fn digest<T: Serialize>(value: &T) -> Digest {
+    let words = risc0_zkvm::serde::to_vec(value).expect("value is not serializable");
+    *Impl::hash_words(&words)
+}
+
+// The trained models, exactly as the guests read them, and the labels they
+// predict. The guests and the host both name these aliases, so a guest that
+// commits other labels than the host decodes does not compile.
+pub type LinearRegressionLabel = u32;
+pub type LinearRegressionModel =
+    LinearRegression<f64, LinearRegressionLabel, DenseMatrix<f64>, Vec<LinearRegressionLabel>>;
+pub type RandomForestLabel = u8;
+pub type RandomForestModel =
+    RandomForestClassifier<f64, RandomForestLabel, DenseMatrix<f64>, Vec<RandomForestLabel>>;
+pub type SvmClassificationLabel = i32;
+/// The parameters of an SVM classifier are not serialized, and the lifetime
+/// is that of the parameters a guest puts back after reading it.
+pub type SvmClassificationModel<'a> =
+    SVC<'a, f64, SvmClassificationLabel, DenseMatrix<f64>, Vec<SvmClassificationLabel>>;
+pub type TeRegressionLabel = u32;
+pub type TeRegressionModel =
+    RandomForestRegressor<f64, TeRegressionLabel, DenseMatrix<f64>, Vec<TeRegressionLabel>>;
+
+/// Any model the generic `inference` guest runs, tagged with its kind.
+#[derive(Serialize, Deserialize)]
+pub enum AnyModel {
+    LinearRegression(LinearRegressionModel),
+    RandomForest(RandomForestModel),
+    SvmClassification(SvmClassificationModel<'static>),
+    TeRegression(TeRegressionModel),
+}
+
+impl AnyModel {
+    pub fn kind(&self) -> ModelKind {
+        match self {
+            AnyModel::LinearRegression(_) => ModelKind::LinearRegression,
+            AnyModel::RandomForest(_) => ModelKind::RandomForest,
+            AnyModel::SvmClassification(_) => ModelKind::SvmClassification,
+            AnyModel::TeRegression(_) => ModelKind::TeRegression,
+        }
+    }
+}
+
+/// The tag of an [AnyModel]. The variants are in the same order, so a kind
+/// written right before a model encodes exactly like the matching [AnyModel],
+/// which lets the host write the tag and a borrowed model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModelKind {
+    LinearRegression,
+    RandomForest,
+    SvmClassification,
+    TeRegression,
+}
+
+/// How the `raw_bytes` guest receives the model and input data: as bytes it
+/// reads with a slice read and decodes itself, instead of values decoded
+/// from risc0's serde words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireFormat {
+    /// MessagePack, which the artifacts under `res/` are stored in.
+    Rmp,
+    Bincode,
+    Postcard,
+}
+
+impl WireFormat {
+    pub const ALL: [WireFormat; 3] = [WireFormat::Rmp, WireFormat::Bincode, WireFormat::Postcard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WireFormat::Rmp => "rmp",
+            WireFormat::Bincode => "bincode",
+            WireFormat::Postcard => "postcard",
+        }
+    }
+}
+
+/// Reinserts the parameters the SVM classifier was trained with into a model
+/// read by a generic guest. smartcore does not serialize them, and they must
+/// be exactly those used in training, see the svm_classification guest.
+#[cfg(target_os = "zkvm")]
+pub fn restore_svc_parameters(model: &mut SvmClassificationModel<'static>) {
+    use smartcore::svm::{svc::SVCParameters, Kernels};
+    let params_same = SVCParameters::default()
+        .with_c(1.0)
+        .with_kernel(Kernels::linear());
+    model.parameters = Some(Box::leak(Box::new(params_same)));
+}
+
+/// Commits the predictions of a generic guest as the dedicated guest of the
+/// model does, preceded by the kind of the model. Journals of different
+/// models can share a layout, so the kind is what tells the host which model
+/// was proven.
+#[cfg(target_os = "zkvm")]
+pub fn commit<M: Serialize, T: Serialize + std::fmt::Debug>(
+    visibility: Visibility,
+    kind: ModelKind,
+    model: &M,
+    x_data: &DenseMatrix<f64>,
+    y_hat: T,
+) {
+    println!("answer: {:?}", &y_hat);
+    risc0_zkvm::guest::env::commit(&(
+        kind,
+        Prediction {
+            model: visibility.model.disclose(model),
+            input: visibility.input.disclose(x_data),
+            y_hat,
+        },
+    ));
+    checkpoint("commit");
+}
+
+/// Predicts `x_data` with `model` and commits the predictions, see [commit].
+/// The generic guests share it, so that they dispatch on the model in one
+/// place.
+#[cfg(target_os = "zkvm")]
+pub fn predict_and_commit(visibility: Visibility, model: AnyModel, x_data: &DenseMatrix<f64>) {
+    let kind = model.kind();
+    match model {
+        AnyModel::LinearRegression(model) => {
+            let y_hat = model.predict(x_data).unwrap();
+            checkpoint("predict");
+            commit(visibility, kind, &model, x_data, y_hat);
+        }
+        AnyModel::RandomForest(model) => {
+            let y_hat = model.predict(x_data).unwrap();
+            checkpoint("predict");
+            commit(visibility, kind, &model, x_data, y_hat);
+        }
+        AnyModel::SvmClassification(mut model) => {
+            restore_svc_parameters(&mut model);
+            let y_hat = model.predict(x_data).unwrap();
+            checkpoint("predict");
+            commit(visibility, kind, &model, x_data, y_hat);
+        }
+        AnyModel::TeRegression(model) => {
+            let y_hat = model.predict(x_data).unwrap();
+            checkpoint("predict");
+            commit(visibility, kind, &model, x_data, y_hat);
+        }
+    }
+}
+
+/// Prefix of the lines that carry cycle checkpoints on the guest's stderr.
+const CHECKPOINT_PREFIX: &str = "cycle-checkpoint ";
+
+/// Reports the cycle count at the end of `phase` to the host. Checkpoints go
+/// to the guest's stderr, which the host reads as a side channel, so they
+/// leave both stdout and the journal alone.
+#[cfg(target_os = "zkvm")]
+pub fn checkpoint(phase: &str) {
+    eprintln!(
+        "{}{} {}",
+        CHECKPOINT_PREFIX,
+        phase,
+        risc0_zkvm::guest::env::get_cycle_count()
+    );
+}
+
+/// The cycles a guest spent in one phase, between two checkpoints.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseCycles {
+    pub phase: String,
+    pub cycles: u64,
+}
+
+/// Returns the lines of a guest's stderr that are not checkpoints, i.e. what
+/// the guest itself wrote there.
+pub fn guest_output(stderr: &str) -> impl Iterator<Item = &str> {
+    stderr
+        .lines()
+        .filter(|line| !line.starts_with(CHECKPOINT_PREFIX))
+}
+
+/// Reads the checkpoints out of a guest's stderr and returns the cycles of
+/// each phase, in order. The first phase counts from the start of the guest.
+pub fn phase_cycles(stderr: &str) -> Vec<PhaseCycles> {
+    let mut previous = 0;
+    stderr
+        .lines()
+        .filter_map(|line| line.strip_prefix(CHECKPOINT_PREFIX))
+        .filter_map(|checkpoint| {
+            let (phase, count) = checkpoint.rsplit_once(' ')?;
+            let count: u64 = count.parse().ok()?;
+            let cycles = count.saturating_sub(previous);
+            previous = count;
+            Some(PhaseCycles {
+                phase: phase.to_string(),
+                cycles,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn phase_cycles_are_deltas_between_checkpoints() {
+        let stderr = "\
+cycle-checkpoint read-model 1200
+some guest output
+cycle-checkpoint read-input 1500
+cycle-checkpoint predict not-a-number
+cycle-checkpoint
+cycle-checkpoint predict 4000
+cycle-checkpoint commit 4250
+";
+        let phase = |phase: &str, cycles| PhaseCycles {
+            phase: phase.to_string(),
+            cycles,
+        };
+        assert_eq!(
+            phase_cycles(stderr),
+            vec![
+                phase("read-model", 1200),
+                phase("read-input", 300),
+                phase("predict", 2500),
+                phase("commit", 250),
+            ]
+        );
+        assert!(phase_cycles("").is_empty());
+        assert_eq!(
+            guest_output(stderr).collect::<Vec<_>>(),
+            vec!["some guest output"]
+        );
+    }
+}
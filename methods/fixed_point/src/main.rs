@@ -0,0 +1,63 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use risc0_zkvm::guest::env;
+use smartcore_ml_common::{
+    checkpoint,
+    fixed::{FixedMatrix, FixedModel},
+    Prediction, Visibility,
+};
+
+risc0_zkvm::guest::entry!(main);
+
+// The fixed-point counterpart of the linear regression and tree ensemble
+// guests. It computes with integers only, so comparing its cycles with those
+// of the smartcore guests shows what emulating f64 costs.
+pub fn main() {
+    // Cycle checkpoints split the run into phases for the host, see
+    // `checkpoint`. This one covers the guest's startup.
+    checkpoint("start");
+
+    // Read which parts of the run to reveal in the journal.
+    let visibility: Visibility = env::read();
+
+    // Read the model, quantized by the host at the scale it carries.
+    let model: FixedModel = env::read();
+    checkpoint("read_model");
+
+    // Read the input data, quantized at the same scale.
+    let x_data: FixedMatrix = env::read();
+    checkpoint("read_input");
+
+    // The host checks that the model predicts its input data without
+    // overflowing before running this guest.
+    let y_hat = model
+        .predict(&x_data)
+        .expect("fixed-point prediction overflowed");
+    checkpoint("predict");
+
+    // The predictions are in fixed point at the model's scale.
+    println!("answer: {:?}", &y_hat);
+
+    // We commit the output to the journal, next to the model and input data,
+    // just like the smartcore guests do.
+    env::commit(&Prediction {
+        model: visibility.model.disclose(&model),
+        input: visibility.input.disclose(&x_data),
+        y_hat,
+    });
+    checkpoint("commit");
+}
@@ -0,0 +1,46 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use risc0_zkvm::guest::env;
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use smartcore_ml_common::{checkpoint, predict_and_commit, AnyModel, Visibility};
+
+risc0_zkvm::guest::entry!(main);
+
+// A single guest for every model. Where the dedicated guests declare the
+// model type they read, this one reads the model tagged with its kind and
+// dispatches on it, so that one image ID serves all models.
+pub fn main() {
+    // Cycle checkpoints split the run into phases for the host, see
+    // `checkpoint`. This one covers the guest's startup.
+    checkpoint("start");
+
+    // Read which parts of the run to reveal in the journal.
+    let visibility: Visibility = env::read();
+
+    // Read the tagged model and the input data.
+    let model: AnyModel = env::read();
+    checkpoint("read_model");
+    let x_data: DenseMatrix<f64> = env::read();
+    checkpoint("read_input");
+
+    predict_and_commit(visibility, model, &x_data);
+
+    println!(
+        "Total cycles for guest code execution: {}",
+        env::get_cycle_count()
+    );
+}
@@ -0,0 +1,72 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use risc0_zkvm::guest::env;
+use serde::de::DeserializeOwned;
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use smartcore_ml_common::{
+    checkpoint, predict_and_commit, AnyModel, ModelKind, Visibility, WireFormat,
+};
+
+risc0_zkvm::guest::entry!(main);
+
+// Like the generic `inference` guest, but the model and input data arrive as
+// raw bytes in a wire format of the host's choosing, which this guest decodes
+// itself. The checkpoints around the decoding tell its cost per format.
+pub fn main() {
+    // Cycle checkpoints split the run into phases for the host, see
+    // `checkpoint`. This one covers the guest's startup.
+    checkpoint("start");
+
+    // Read which parts of the run to reveal in the journal, then which model
+    // follows and how it is encoded.
+    let visibility: Visibility = env::read();
+    let (kind, format): (ModelKind, WireFormat) = env::read();
+
+    let model_bytes = read_bytes();
+    checkpoint("read_model");
+    let data_bytes = read_bytes();
+    checkpoint("read_input");
+    let x_data: DenseMatrix<f64> = decode(format, &data_bytes);
+    checkpoint("decode_input");
+
+    let model = match kind {
+        ModelKind::LinearRegression => AnyModel::LinearRegression(decode(format, &model_bytes)),
+        ModelKind::RandomForest => AnyModel::RandomForest(decode(format, &model_bytes)),
+        ModelKind::SvmClassification => AnyModel::SvmClassification(decode(format, &model_bytes)),
+        ModelKind::TeRegression => AnyModel::TeRegression(decode(format, &model_bytes)),
+    };
+    checkpoint("decode_model");
+
+    predict_and_commit(visibility, model, &x_data);
+}
+
+// Reads bytes the host wrote after their length, without going through
+// risc0's serde.
+fn read_bytes() -> Vec<u8> {
+    let len: u32 = env::read();
+    let mut bytes = vec![0u8; len as usize];
+    env::read_slice(&mut bytes);
+    bytes
+}
+
+fn decode<T: DeserializeOwned>(format: WireFormat, bytes: &[u8]) -> T {
+    match format {
+        WireFormat::Rmp => rmp_serde::from_slice(bytes).unwrap(),
+        WireFormat::Bincode => bincode::deserialize(bytes).unwrap(),
+        WireFormat::Postcard => postcard::from_bytes(bytes).unwrap(),
+    }
+}
@@ -17,23 +17,30 @@
 use risc0_zkvm::guest::env;
 use smartcore::{
     linalg::basic::matrix::DenseMatrix,
-    svm::{
-        svc::{SVCParameters, SVC},
-        Kernels,
-    },
+    svm::{svc::SVCParameters, Kernels},
 };
+use smartcore_ml_common::{checkpoint, Prediction, SvmClassificationModel, Visibility};
 
 risc0_zkvm::guest::entry!(main);
 
 pub fn main() {
+    // Cycle checkpoints split the run into phases for the host, see
+    // `checkpoint`. This one covers the guest's startup.
+    checkpoint("start");
+
+    // Read which parts of the run to reveal in the journal.
+    let visibility: Visibility = env::read();
+
     // Read the model from the host into a SmartCore Decesion Tree model object.
     // We MUST explicitly declare the correct type in order for deserialization to be
     // successful.
     // Read the model from the host into a SmartCore SVC object.
-    let mut model: SVC<f64, i32, DenseMatrix<f64>, Vec<i32>> = env::read();
+    let mut model: SvmClassificationModel = env::read();
+    checkpoint("read_model");
 
     // Read the input data into a DenseMatrix.
     let x_data: DenseMatrix<f64> = env::read();
+    checkpoint("read_input");
 
     // Calling predict on a deserialized SVM model will result in an error due to the missing parameters field.
     // We need to use THE EXACT SAME SVCParameters that we used to train the model.  Adjust the code below in accordance with how you trained the SVC model.
@@ -46,13 +53,23 @@ pub fn main() {
 
     // We call the predict() function on our trained model to perform inference.
     let y_hat = model.predict(&x_data).unwrap();
+    checkpoint("predict");
 
     // This line is optional and can be commented out, but it's useful to see
     // the output of the computation before the proving step begins.
     println!("answer: {:?}", &y_hat);
 
-    // We commit the output to the journal.
-    env::commit(&y_hat);
+    // We commit the output to the journal, next to the model and input data.
+    // Private parts are committed only as their SHA-256 digests, computed with
+    // the SHA accelerator, which binds the output to them without revealing
+    // them. The parameters set above are not serialized, so the model is
+    // disclosed exactly as the host sent it.
+    env::commit(&Prediction {
+        model: visibility.model.disclose(&model),
+        input: visibility.input.disclose(&x_data),
+        y_hat,
+    });
+    checkpoint("commit");
 
     // Logging the total cycle count is optional, though it's quite useful for benchmarking
     // the various operations in the guest code. env::get_cycle_count() can be
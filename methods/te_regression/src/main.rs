@@ -15,29 +15,47 @@
 #![no_main]
 
 use risc0_zkvm::guest::env;
-use smartcore::{ensemble::random_forest_regressor::*, linalg::basic::matrix::DenseMatrix};
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use smartcore_ml_common::{checkpoint, Prediction, TeRegressionModel, Visibility};
 
 risc0_zkvm::guest::entry!(main);
 
 pub fn main() {
+    // Cycle checkpoints split the run into phases for the host, see
+    // `checkpoint`. This one covers the guest's startup.
+    checkpoint("start");
+
+    // Read which parts of the run to reveal in the journal.
+    let visibility: Visibility = env::read();
+
     // Read the model from the host into a SmartCore Decesion Tree model object.
     // We MUST explicitly declare the correct type in order for deserialization to be
     // successful.
-    type Model = RandomForestRegressor<f64, u32, DenseMatrix<f64>, Vec<u32>>;
-    let trained_model: Model = env::read();
+    let trained_model: TeRegressionModel = env::read();
+    checkpoint("read_model");
 
     // Read the input data into a DenseMatrix.
     let x_data: DenseMatrix<f64> = env::read();
+    checkpoint("read_input");
 
     // We call the predict() function on our trained model to perform inference.
     let y_hat = trained_model.predict(&x_data).unwrap();
+    checkpoint("predict");
 
     // This line is optional and can be commented out, but it's useful to see
     // the output of the computation before the proving step begins.
     println!("answer: {:?}", &y_hat);
 
-    // We commit the output to the journal.
-    env::commit(&y_hat);
+    // We commit the output to the journal, next to the model and input data.
+    // Private parts are committed only as their SHA-256 digests, computed with
+    // the SHA accelerator, which binds the output to them without revealing
+    // them.
+    env::commit(&Prediction {
+        model: visibility.model.disclose(&trained_model),
+        input: visibility.input.disclose(&x_data),
+        y_hat,
+    });
+    checkpoint("commit");
 
     // Logging the total cycle count is optional, though it's quite useful for benchmarking
     // the various operations in the guest code. env::get_cycle_count() can be
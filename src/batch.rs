@@ -0,0 +1,179 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reading batches of input rows from CSV or NPY files.
+
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// Reads a batch of input rows. Files ending in `.npy` are read as a 2-D
+/// little endian `float64` NumPy array; any other file is read as CSV, with an
+/// optional header line.
+pub fn read_batch(path: &Path) -> Result<DenseMatrix<f64>> {
+    let rows = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("npy") => read_npy(path)?,
+        _ => read_csv(path)?,
+    };
+    let cols = match rows.first() {
+        Some(row) => row.len(),
+        None => return Err(invalid(path, "no rows".to_string())),
+    };
+    if let Some(i) = rows.iter().position(|row| row.len() != cols) {
+        return Err(invalid(
+            path,
+            format!(
+                "row {} has {} columns, expected {}",
+                i + 1,
+                rows[i].len(),
+                cols
+            ),
+        ));
+    }
+    Ok(DenseMatrix::from_2d_vec(&rows))
+}
+
+fn invalid(path: &Path, reason: String) -> Error {
+    Error::Batch {
+        path: path.to_path_buf(),
+        reason,
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|source| Error::MissingArtifact {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn read_csv(path: &Path) -> Result<Vec<Vec<f64>>> {
+    let bytes = read_file(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let mut rows = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row: std::result::Result<Vec<f64>, _> =
+            line.split(',').map(|value| value.trim().parse()).collect();
+        match row {
+            Ok(row) => rows.push(row),
+            // The first line may be a header, as in iris_input_data.csv.
+            Err(_) if i == 0 => continue,
+            Err(err) => return Err(invalid(path, format!("line {}: {}", i + 1, err))),
+        }
+    }
+    Ok(rows)
+}
+
+fn read_npy(path: &Path) -> Result<Vec<Vec<f64>>> {
+    let bytes = read_file(path)?;
+    let truncated = || invalid(path, "truncated NPY file".to_string());
+    if bytes.get(..6) != Some(&b"\x93NUMPY"[..]) {
+        return Err(invalid(path, "not an NPY file".to_string()));
+    }
+
+    // Version 1 stores the header length in 2 bytes, versions 2 and 3 in 4.
+    let (header_len, header_start) = match bytes.get(6) {
+        Some(1) => {
+            let len = bytes.get(8..10).ok_or_else(truncated)?;
+            (u16::from_le_bytes([len[0], len[1]]) as usize, 10)
+        }
+        Some(2) | Some(3) => {
+            let len = bytes.get(8..12).ok_or_else(truncated)?;
+            (
+                u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize,
+                12,
+            )
+        }
+        _ => return Err(invalid(path, "unsupported NPY version".to_string())),
+    };
+    let data_start = header_start + header_len;
+    let header = bytes.get(header_start..data_start).ok_or_else(truncated)?;
+    let header = String::from_utf8_lossy(header);
+
+    if !header.contains("'descr': '<f8'") {
+        return Err(invalid(
+            path,
+            "only float64 arrays are supported".to_string(),
+        ));
+    }
+    if header.contains("'fortran_order': True") {
+        return Err(invalid(
+            path,
+            "Fortran ordered arrays are not supported".to_string(),
+        ));
+    }
+    let shape: Vec<usize> = header
+        .split("'shape': (")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .map(|dims| {
+            dims.split(',')
+                .map(str::trim)
+                .filter(|dim| !dim.is_empty())
+                .filter_map(|dim| dim.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let (nrows, ncols) = match shape[..] {
+        [nrows, ncols] => (nrows, ncols),
+        _ => return Err(invalid(path, "expected a 2-D array".to_string())),
+    };
+
+    let data = &bytes[data_start..];
+    if ncols == 0 || data.len() != nrows * ncols * 8 {
+        return Err(truncated());
+    }
+    let values: Vec<f64> = data
+        .chunks_exact(8)
+        .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    Ok(values.chunks(ncols).map(|row| row.to_vec()).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use smartcore::linalg::basic::arrays::Array;
+
+    #[test]
+    fn csv_with_header() {
+        let batch =
+            read_batch(Path::new("./notebooks/random_forests/iris_input_data.csv")).unwrap();
+        assert_eq!(batch.shape(), (150, 4));
+        assert_eq!(*batch.get((0, 0)), 5.1);
+    }
+
+    #[test]
+    fn npy() {
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }";
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for value in [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.npy");
+        fs::write(&path, bytes).unwrap();
+
+        let batch = read_batch(&path).unwrap();
+        assert_eq!(batch.shape(), (2, 3));
+        assert_eq!(*batch.get((1, 2)), 6.0);
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Errors of the host binary, each mapped to its own process exit code so
+//! that the benchmark harness can tell why a run failed.
+
+use std::io;
+use std::path::PathBuf;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("model type not recognized: {0}")]
+    UnknownModel(String),
+
+    #[error("failed to read artifact {}: {source}", path.display())]
+    MissingArtifact { path: PathBuf, source: io::Error },
+
+    #[error("artifact {} is not a JSON byte array: {source}", path.display())]
+    ArtifactJson {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error("invalid batch file {}: {reason}", path.display())]
+    Batch { path: PathBuf, reason: String },
+
+    #[error("sweep failed: {0}")]
+    Sweep(String),
+
+    #[error("failed to configure the prover threads: {0}")]
+    Threads(String),
+
+    #[error("failed to create profile directory {}: {source}", path.display())]
+    Profile { path: PathBuf, source: io::Error },
+
+    #[error("failed to deserialize the {what} from rmp bytes: {source}")]
+    Rmp {
+        what: &'static str,
+        source: rmp_serde::decode::Error,
+    },
+
+    #[error("failed to build the executor environment: {0}")]
+    ExecutorEnv(anyhow::Error),
+
+    #[error("executor failed: {0}")]
+    Executor(anyhow::Error),
+
+    #[error("prover failed: {0}")]
+    Prover(anyhow::Error),
+
+    #[error("failed to decode the journal: {0}")]
+    Journal(String),
+
+    #[error("failed to read or write receipt {}: {source}", path.display())]
+    ReceiptIo { path: PathBuf, source: io::Error },
+
+    #[error("receipt {} could not be encoded or decoded: {source}", path.display())]
+    ReceiptFormat {
+        path: PathBuf,
+        source: bincode::Error,
+    },
+
+    #[error("receipt failed to verify: {0}")]
+    Verification(String),
+
+    #[error("cannot run the model in fixed point: {0}")]
+    FixedPoint(String),
+
+    #[error("failed to encode the guest input as {format}: {reason}")]
+    Encode {
+        format: &'static str,
+        reason: String,
+    },
+}
+
+impl Error {
+    /// The exit code of the host binary when it stops with this error. Code 1
+    /// is unused, 2 is clap's for command line usage errors, and panics exit
+    /// with 101.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::UnknownModel(_) => 3,
+            Error::MissingArtifact { .. } => 4,
+            Error::ArtifactJson { .. } => 5,
+            Error::Rmp { .. } => 6,
+            Error::ExecutorEnv(_) => 7,
+            Error::Executor(_) => 8,
+            Error::Prover(_) => 9,
+            Error::Journal(_) => 10,
+            Error::ReceiptIo { .. } | Error::ReceiptFormat { .. } => 11,
+            Error::Verification(_) => 12,
+            Error::Batch { .. } => 13,
+            Error::Sweep(_) => 14,
+            Error::Profile { .. } => 15,
+            Error::Threads(_) => 16,
+            Error::FixedPoint(_) => 17,
+            Error::Encode { .. } => 18,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A description of the machine and build that benchmark numbers were
+//! measured on, so that results from different machines can be told apart.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::process::Command;
+
+/// The machine, toolchain and build a benchmark session ran on. Values that
+/// cannot be determined on the current platform are left empty.
+#[derive(Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub hostname: Option<String>,
+    pub cpu: Option<String>,
+    /// Number of cores available to this process.
+    pub cores: usize,
+    pub memory_kb: Option<u64>,
+    /// Operating system and architecture this binary was built for, e.g.
+    /// `linux x86_64`.
+    pub os: String,
+    /// Kernel name and release, as printed by `uname -sr`.
+    pub os_version: Option<String>,
+    /// Version of the `rustc` this binary was built with.
+    pub rustc: String,
+    pub risc0_zkvm: String,
+    /// Cargo features this binary was built with.
+    pub features: Vec<String>,
+    pub git_commit: Option<String>,
+    /// Whether the working tree had uncommitted changes.
+    pub git_dirty: Option<bool>,
+}
+
+impl Fingerprint {
+    pub fn collect() -> Self {
+        let features = [
+            ("prove", cfg!(feature = "prove")),
+            ("cuda", cfg!(feature = "cuda")),
+            ("metal", cfg!(feature = "metal")),
+        ];
+        Fingerprint {
+            hostname: command_output("hostname", &[]),
+            cpu: cpu(),
+            cores: std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+            memory_kb: memory_kb(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            os_version: command_output("uname", &["-sr"]),
+            rustc: env!("RUSTC_VERSION").to_string(),
+            risc0_zkvm: risc0_zkvm::VERSION.to_string(),
+            features: features
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            git_commit: command_output("git", &["rev-parse", "HEAD"]),
+            git_dirty: command_output("git", &["status", "--porcelain"])
+                .map(|status| !status.is_empty()),
+        }
+    }
+}
+
+/// Runs `program` and returns its trimmed stdout, or `None` when it could not
+/// be run or failed.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn cpu() -> Option<String> {
+    if cfg!(target_os = "macos") {
+        return command_output("sysctl", &["-n", "machdep.cpu.brand_string"]);
+    }
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    cpuinfo
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim() == "model name")
+        .map(|(_, value)| value.trim().to_string())
+}
+
+fn memory_kb() -> Option<u64> {
+    if cfg!(target_os = "macos") {
+        return command_output("sysctl", &["-n", "hw.memsize"])?
+            .parse::<u64>()
+            .ok()
+            .map(|bytes| bytes / 1024);
+    }
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fingerprint_describes_this_build() {
+        let fingerprint = Fingerprint::collect();
+        assert!(fingerprint.cores > 0);
+        assert_eq!(fingerprint.risc0_zkvm, risc0_zkvm::VERSION);
+        assert!(fingerprint.rustc.starts_with("rustc "));
+        assert!(fingerprint.os.starts_with(std::env::consts::OS));
+        assert_eq!(
+            fingerprint.features.contains(&"prove".to_string()),
+            cfg!(feature = "prove")
+        );
+    }
+}
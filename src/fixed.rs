@@ -0,0 +1,352 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fixed-point runs of the linear regression and tree ensembles, see
+//! [smartcore_ml_common::fixed]. The host reduces the smartcore models to
+//! their parameters, quantizes them and their input data for the
+//! `fixed_point` guest, and computes the same predictions in `f64` to measure
+//! how far the guest's are off.
+
+use serde::Serialize;
+use serde_json::Value;
+use smartcore::linalg::basic::{arrays::Array, matrix::DenseMatrix};
+use smartcore_ml_common::fixed::{Aggregate, FixedMatrix, FixedModel, Node, PlainModel, Split};
+use smartcore_ml_common::{LinearRegressionModel, Prediction};
+
+use crate::error::{Error, Result};
+
+/// Fractional bits used when none are given, the 16.16 format of orion's
+/// FP16x16.
+pub const DEFAULT_SCALE: u32 = 16;
+
+/// The journal of the `fixed_point` guest. Its predictions are in fixed
+/// point at the model's scale.
+pub type Committed = Prediction<FixedModel, FixedMatrix, Vec<i64>>;
+
+/// Converts `value` to fixed point with `scale` fractional bits. Values
+/// that do not fit in an `i64` at that scale are rejected rather than
+/// saturated, which would skew the reported deviation.
+pub fn to_fixed(value: f64, scale: u32) -> Result<i64> {
+    let bound = 2f64.powi(63);
+    let fixed = (value * (1u64 << scale) as f64).round();
+    if (-bound..bound).contains(&fixed) {
+        Ok(fixed as i64)
+    } else {
+        Err(Error::FixedPoint(format!(
+            "{} does not fit in fixed point with {} fractional bits",
+            value, scale
+        )))
+    }
+}
+
+pub fn from_fixed(value: i64, scale: u32) -> f64 {
+    value as f64 / (1u64 << scale) as f64
+}
+
+/// Quantizes `model` to `scale` fractional bits. The leaves of a voting
+/// forest hold class indices, which are kept as they are.
+pub fn quantize(model: &PlainModel<f64>, scale: u32) -> Result<FixedModel> {
+    let fixed = |value: f64| to_fixed(value, scale);
+    let model = match model {
+        PlainModel::Linear {
+            coefficients,
+            intercept,
+        } => PlainModel::Linear {
+            coefficients: coefficients
+                .iter()
+                .map(|&c| fixed(c))
+                .collect::<Result<_>>()?,
+            intercept: fixed(*intercept)?,
+        },
+        PlainModel::Forest { trees, aggregate } => {
+            let output = |value: f64| match aggregate {
+                Aggregate::Mean => fixed(value),
+                Aggregate::Vote { .. } => Ok(value as i64),
+            };
+            PlainModel::Forest {
+                trees: trees
+                    .iter()
+                    .map(|tree| {
+                        tree.iter()
+                            .map(|node| {
+                                Ok(Node {
+                                    split: match &node.split {
+                                        Some(split) => Some(Split {
+                                            feature: split.feature,
+                                            threshold: fixed(split.threshold)?,
+                                            true_child: split.true_child,
+                                            false_child: split.false_child,
+                                        }),
+                                        None => None,
+                                    },
+                                    output: output(node.output)?,
+                                })
+                            })
+                            .collect::<Result<Vec<_>>>()
+                    })
+                    .collect::<Result<_>>()?,
+                aggregate: match aggregate {
+                    Aggregate::Mean => Aggregate::Mean,
+                    Aggregate::Vote { classes } => Aggregate::Vote {
+                        classes: classes
+                            .iter()
+                            .map(|&class| fixed(class))
+                            .collect::<Result<_>>()?,
+                    },
+                },
+            }
+        }
+    };
+    Ok(FixedModel { scale, model })
+}
+
+pub fn quantize_matrix(data: &DenseMatrix<f64>, scale: u32) -> Result<FixedMatrix> {
+    let (rows, cols) = data.shape();
+    Ok(FixedMatrix {
+        ncols: cols as u32,
+        values: (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| to_fixed(*data.get((row, col)), scale)))
+            .collect::<Result<_>>()?,
+    })
+}
+
+/// Predicts every row of `data` with `model` in `f64`, the way smartcore
+/// does before converting the predictions to its label type.
+pub fn predict(model: &PlainModel<f64>, data: &DenseMatrix<f64>) -> Result<Vec<f64>> {
+    let (rows, cols) = data.shape();
+    (0..rows)
+        .map(|row| {
+            let x: Vec<f64> = (0..cols).map(|col| *data.get((row, col))).collect();
+            model
+                .predict(&x, 0)
+                .ok_or_else(|| layout("a leaf names a class the forest does not have"))
+        })
+        .collect()
+}
+
+/// Predicts every row of `data` with `model` in fixed point, as the guest
+/// will, failing when the arithmetic overflows `i64` at the model's scale.
+pub fn predict_fixed(model: &FixedModel, data: &FixedMatrix) -> Result<Vec<i64>> {
+    model.predict(data).ok_or_else(|| {
+        Error::FixedPoint(format!(
+            "the predictions overflow fixed point with {} fractional bits",
+            model.scale
+        ))
+    })
+}
+
+pub fn linear(model: &LinearRegressionModel) -> PlainModel<f64> {
+    PlainModel::Linear {
+        coefficients: model.coefficients().iterator(0).copied().collect(),
+        intercept: *model.intercept(),
+    }
+}
+
+/// Reduces a random forest classifier to its trees and classes. smartcore
+/// does not expose the nodes of its trees, so they are read from the
+/// model's serde representation.
+pub fn forest_classifier<M: Serialize>(model: &M) -> Result<PlainModel<f64>> {
+    let model = to_value(model)?;
+    let classes = model["classes"]
+        .as_array()
+        .ok_or_else(|| layout("the forest has no classes"))?
+        .iter()
+        .map(|class| {
+            class
+                .as_f64()
+                .ok_or_else(|| layout("a class is not a number"))
+        })
+        .collect::<Result<_>>()?;
+    Ok(PlainModel::Forest {
+        trees: trees(&model)?,
+        aggregate: Aggregate::Vote { classes },
+    })
+}
+
+/// Reduces a random forest regressor to its trees, see
+/// [forest_classifier].
+pub fn forest_regressor<M: Serialize>(model: &M) -> Result<PlainModel<f64>> {
+    Ok(PlainModel::Forest {
+        trees: trees(&to_value(model)?)?,
+        aggregate: Aggregate::Mean,
+    })
+}
+
+fn to_value<M: Serialize>(model: &M) -> Result<Value> {
+    serde_json::to_value(model).map_err(|err| layout(&err.to_string()))
+}
+
+fn layout(reason: &str) -> Error {
+    Error::FixedPoint(format!("unexpected smartcore model layout: {}", reason))
+}
+
+/// Reads the trees of a forest. Predicting averages or counts over them and
+/// starts at the first node of each, so neither may be empty.
+fn trees(model: &Value) -> Result<Vec<Vec<Node<f64>>>> {
+    let trees = model["trees"]
+        .as_array()
+        .filter(|trees| !trees.is_empty())
+        .ok_or_else(|| layout("the forest has no trees"))?;
+    trees
+        .iter()
+        .map(|tree| {
+            tree["nodes"]
+                .as_array()
+                .filter(|nodes| !nodes.is_empty())
+                .ok_or_else(|| layout("a tree has no nodes"))?
+                .iter()
+                .map(node)
+                .collect()
+        })
+        .collect()
+}
+
+fn node(node: &Value) -> Result<Node<f64>> {
+    let index = |field: &str| node[field].as_u64().map(|index| index as u32);
+    // Like smartcore, a node without children is a leaf.
+    let split = match (index("true_child"), index("false_child")) {
+        (Some(true_child), Some(false_child)) => Some(Split {
+            feature: index("split_feature").ok_or_else(|| layout("a split has no feature"))?,
+            threshold: node["split_value"]
+                .as_f64()
+                .ok_or_else(|| layout("a split has no threshold"))?,
+            true_child,
+            false_child,
+        }),
+        _ => None,
+    };
+    Ok(Node {
+        split,
+        output: node["output"]
+            .as_f64()
+            .ok_or_else(|| layout("a node has no output"))?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::host::{load_inputs, RunOptions};
+    use crate::models::{BenchModel, LinearRegressions, RandomForests, TeRegressions};
+    use smartcore::api::Predictor;
+
+    /// Checks that the parameters read out of the model of `M` predict what
+    /// smartcore predicts, once converted to its integer labels.
+    fn predicts_like_smartcore<M: BenchModel>() {
+        let (model, data) = load_inputs::<M::Model>(&RunOptions::default(), M::ARTIFACT).unwrap();
+        let expected: Vec<f64> =
+            serde_json::from_value(serde_json::to_value(model.predict(&data).unwrap()).unwrap())
+                .unwrap();
+        let plain = M::plain_model(&model).unwrap();
+        let actual: Vec<f64> = predict(&plain, &data)
+            .unwrap()
+            .iter()
+            .map(|y| y.trunc())
+            .collect();
+        assert_eq!(actual, expected, "{}", M::NAME);
+    }
+
+    #[test]
+    fn plain_models_predict_like_smartcore() {
+        predicts_like_smartcore::<LinearRegressions>();
+        predicts_like_smartcore::<RandomForests>();
+        predicts_like_smartcore::<TeRegressions>();
+    }
+
+    #[test]
+    fn values_out_of_fixed_point_range_are_rejected() {
+        assert_eq!(to_fixed(-1.5, 4).unwrap(), -24);
+        assert_eq!(to_fixed(2f64.powi(30), 32).unwrap(), 1 << 62);
+        assert!(matches!(
+            to_fixed(2f64.powi(31), 32),
+            Err(Error::FixedPoint(_))
+        ));
+        assert!(matches!(
+            to_fixed(-2f64.powi(32), 32),
+            Err(Error::FixedPoint(_))
+        ));
+        assert!(matches!(to_fixed(f64::NAN, 16), Err(Error::FixedPoint(_))));
+    }
+
+    #[test]
+    fn overflowing_predictions_are_rejected() {
+        let scale = 32;
+        let plain = PlainModel::Linear {
+            coefficients: vec![2f64.powi(29), 2f64.powi(29)],
+            intercept: 0.0,
+        };
+        let model = quantize(&plain, scale).unwrap();
+        let data = FixedMatrix {
+            ncols: 2,
+            values: vec![to_fixed(2f64.powi(30), scale).unwrap(); 2],
+        };
+        assert!(matches!(
+            predict_fixed(&model, &data),
+            Err(Error::FixedPoint(_))
+        ));
+        let data = FixedMatrix {
+            ncols: 2,
+            values: vec![to_fixed(1.0, scale).unwrap(); 2],
+        };
+        assert_eq!(
+            predict_fixed(&model, &data).unwrap(),
+            vec![to_fixed(2f64.powi(30), scale).unwrap()]
+        );
+    }
+
+    #[test]
+    fn empty_forests_are_rejected() {
+        let empty = serde_json::json!({ "trees": [] });
+        assert!(matches!(
+            forest_regressor(&empty),
+            Err(Error::FixedPoint(_))
+        ));
+        let leafless = serde_json::json!({ "trees": [{ "nodes": [] }] });
+        assert!(matches!(
+            forest_regressor(&leafless),
+            Err(Error::FixedPoint(_))
+        ));
+    }
+
+    #[test]
+    fn quantized_linear_regression_stays_within_its_rounding_error() {
+        let opts = RunOptions::default();
+        let (model, data) = load_inputs(&opts, LinearRegressions::ARTIFACT).unwrap();
+        let plain = LinearRegressions::plain_model(&model).unwrap();
+        let coefficients = match &plain {
+            PlainModel::Linear { coefficients, .. } => coefficients.clone(),
+            PlainModel::Forest { .. } => unreachable!(),
+        };
+        let expected = predict(&plain, &data).unwrap();
+        for scale in [8, DEFAULT_SCALE, 24] {
+            let actual = quantize(&plain, scale)
+                .unwrap()
+                .predict(&quantize_matrix(&data, scale).unwrap())
+                .unwrap();
+            for (row, (actual, expected)) in actual.iter().zip(&expected).enumerate() {
+                // Each product is off by at most half a unit times the other
+                // factor, plus one unit from the shift, and the intercept by
+                // half a unit.
+                let units: f64 = coefficients
+                    .iter()
+                    .enumerate()
+                    .map(|(col, c)| (c.abs() + data.get((row, col)).abs()) / 2.0 + 1.0)
+                    .sum::<f64>()
+                    + 1.0;
+                let tolerance = units / (1u64 << scale) as f64;
+                assert!((from_fixed(*actual, scale) - expected).abs() <= tolerance);
+            }
+        }
+    }
+}
@@ -0,0 +1,446 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Loading artifacts and running guests in the risc0 executor and prover.
+
+use risc0_zkvm::{
+    default_executor, default_prover, ExecutorEnv, ExecutorEnvBuilder, Journal, Receipt,
+    SessionInfo,
+};
+#[cfg(feature = "prove")]
+use risc0_zkvm::{get_prover_server, ProverOpts};
+use serde::{de::DeserializeOwned, Serialize};
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use smartcore_ml_common::{guest_output, phase_cycles, PhaseCycles, Visibility};
+
+use crate::batch::read_batch;
+use crate::error::{Error, Result};
+use crate::models::Guest;
+use crate::synthetic::synthetic_rows;
+
+/// Settings shared by every guest run.
+#[derive(Clone)]
+pub struct RunOptions {
+    /// Send guest stdout to stderr, keeping stdout free for a JSON report.
+    pub guest_stdout_to_stderr: bool,
+    /// Directory containing `res/`. Empty means the current directory.
+    pub workdir: PathBuf,
+    /// Model artifact to use instead of the one shipped under `res/`.
+    pub model_file: Option<PathBuf>,
+    /// Input data artifact to use instead of the one shipped under `res/`.
+    pub data_file: Option<PathBuf>,
+    /// CSV or NPY file of input rows to use instead of an input data
+    /// artifact, see [read_batch].
+    pub batch_file: Option<PathBuf>,
+    /// The guest program to run.
+    pub guest: Guest,
+    /// Which of the model and input data the guest reveals in the journal.
+    pub visibility: Visibility,
+    /// Number of synthetic rows to predict instead of the input data, which
+    /// then only serves as the template for [synthetic_rows].
+    pub synthetic_rows: Option<usize>,
+    /// Segment size limit as a power of two, `None` for the risc0 default.
+    /// Must be in [SEGMENT_PO2_RANGE].
+    pub segment_limit_po2: Option<u32>,
+    /// Directory to write a pprof profile of each executed guest to, see
+    /// [RunOptions::profile_path].
+    pub profile_dir: Option<PathBuf>,
+    /// Compress the final receipt into a succinct receipt.
+    pub succinct: bool,
+    /// Number of measured proofs, at least one.
+    pub runs: usize,
+    /// Number of proofs made before the measured ones and left out of the
+    /// timing statistics.
+    pub warmup: usize,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            guest_stdout_to_stderr: false,
+            workdir: PathBuf::new(),
+            model_file: None,
+            data_file: None,
+            batch_file: None,
+            guest: Guest::default(),
+            visibility: Visibility::default(),
+            synthetic_rows: None,
+            segment_limit_po2: None,
+            profile_dir: None,
+            succinct: false,
+            runs: 1,
+            warmup: 0,
+        }
+    }
+}
+
+impl RunOptions {
+    /// Returns the artifact paths for the artifact prefix `name`, e.g.
+    /// `linear_regression`, honouring `model_file` and `data_file`.
+    ///
+    /// The serialized trained model and input data are read from these files.
+    /// Alternatively, the model can be trained in the host and/or data can be
+    /// built by hand as a smartcore DenseMatrix; be sure to import the
+    /// corresponding smartcore modules and serialize the model and data to
+    /// byte arrays before transfer to the guest.
+    pub fn artifact_paths(&self, name: &str) -> ArtifactPaths {
+        let res = self.workdir.join("res");
+        ArtifactPaths {
+            model: self.model_file.clone().unwrap_or_else(|| {
+                res.join("ml-model")
+                    .join(format!("{}_model_bytes.json", name))
+            }),
+            data: self.data_file.clone().unwrap_or_else(|| {
+                res.join("input-data")
+                    .join(format!("{}_data_bytes.json", name))
+            }),
+        }
+    }
+
+    /// Returns the file the guest profile of model `name` is written to when
+    /// profiling is enabled.
+    pub fn profile_path(&self, name: &str) -> Option<PathBuf> {
+        self.profile_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.pb", name)))
+    }
+}
+
+/// The segment size limits, as powers of two, that risc0 0.19 can prove: its
+/// STARKs cover traces of 2^13 to 2^24 cycles.
+pub const SEGMENT_PO2_RANGE: std::ops::RangeInclusive<u32> = 13..=24;
+
+/// Where a model and its input data are read from.
+pub struct ArtifactPaths {
+    pub model: PathBuf,
+    pub data: PathBuf,
+}
+
+/// Reads the rmp bytes of an artifact. Files ending in `.json` store the bytes
+/// as a JSON array of numbers, as written by the notebooks; any other file is
+/// read as raw rmp bytes, as written by `rmp_serde::to_vec`.
+pub fn read_artifact_bytes(path: &Path) -> Result<Vec<u8>> {
+    let missing = |source| Error::MissingArtifact {
+        path: path.to_path_buf(),
+        source,
+    };
+    if path.extension().map_or(false, |ext| ext == "json") {
+        let json = fs::read_to_string(path).map_err(missing)?;
+        // Convert the JSON into a byte array.
+        serde_json::from_str(&json).map_err(|source| Error::ArtifactJson {
+            path: path.to_path_buf(),
+            source,
+        })
+    } else {
+        fs::read(path).map_err(missing)
+    }
+}
+
+pub fn load_model<M: DeserializeOwned>(path: &Path) -> Result<M> {
+    let model_bytes = read_artifact_bytes(path)?;
+    // Deserialize the model from rmp into native rust types.
+    rmp_serde::from_slice(&model_bytes).map_err(|source| Error::Rmp {
+        what: "model",
+        source,
+    })
+}
+
+pub fn load_data(path: &Path) -> Result<DenseMatrix<f64>> {
+    let data_bytes = read_artifact_bytes(path)?;
+    // Deserialize the data from rmp into native rust types.
+    rmp_serde::from_slice(&data_bytes).map_err(|source| Error::Rmp {
+        what: "input data",
+        source,
+    })
+}
+
+pub fn load_artifacts<M: DeserializeOwned>(paths: &ArtifactPaths) -> Result<(M, DenseMatrix<f64>)> {
+    Ok((load_model(&paths.model)?, load_data(&paths.data)?))
+}
+
+/// Loads the model and input data of a run for the artifact prefix `name`,
+/// reading the input data from `opts.batch_file` when one is given and
+/// replacing it with `opts.synthetic_rows` rows of the same shape when asked
+/// to.
+pub fn load_inputs<M: DeserializeOwned>(
+    opts: &RunOptions,
+    name: &str,
+) -> Result<(M, DenseMatrix<f64>)> {
+    let paths = opts.artifact_paths(name);
+    let (model, data) = match &opts.batch_file {
+        Some(batch_file) => (load_model(&paths.model)?, read_batch(batch_file)?),
+        None => load_artifacts(&paths)?,
+    };
+    match opts.synthetic_rows {
+        Some(rows) => Ok((model, synthetic_rows(&data, rows))),
+        None => Ok((model, data)),
+    }
+}
+
+/// Builds the environment of a guest run. The guest reads the visibility
+/// first and then whatever `write_inputs` writes.
+fn build_env<'a>(
+    opts: &RunOptions,
+    profile: Option<&Path>,
+    stderr: impl Write + 'a,
+    write_inputs: impl FnOnce(&mut ExecutorEnvBuilder<'a>) -> anyhow::Result<()>,
+) -> Result<ExecutorEnv<'a>> {
+    let mut builder = ExecutorEnv::builder();
+    builder
+        .write(&opts.visibility)
+        .map_err(Error::ExecutorEnv)?;
+    write_inputs(&mut builder).map_err(Error::ExecutorEnv)?;
+    if opts.guest_stdout_to_stderr {
+        builder.stdout(std::io::stderr());
+    }
+    // The guest's stderr carries its cycle checkpoints, see `phase_cycles`.
+    builder.stderr(stderr);
+    if let Some(po2) = opts.segment_limit_po2 {
+        builder.segment_limit_po2(po2);
+    }
+    if let Some(path) = profile {
+        builder.enable_profiler(path);
+    }
+    builder.build().map_err(Error::ExecutorEnv)
+}
+
+/// Runs the guest in the executor and returns its session, the cycles of
+/// each phase it reported and the execution time. When `profile` is given,
+/// the executor also samples the guest's call stacks and writes them to that
+/// file as a pprof profile.
+pub fn execute<T: Serialize, D: Serialize>(
+    model: &T,
+    data: &D,
+    elf: &[u8],
+    opts: &RunOptions,
+    profile: Option<&Path>,
+) -> Result<(SessionInfo, Vec<PhaseCycles>, Duration)> {
+    execute_with(elf, opts, profile, |builder| {
+        builder.write(model)?.write(data)?;
+        Ok(())
+    })
+}
+
+/// Runs the guest in the executor like [execute], but writes the model and
+/// input data as raw bytes after `header`, each preceded by its length, for
+/// the guest to read with `env::read_slice` and decode itself.
+pub fn execute_bytes<T: Serialize>(
+    header: &T,
+    model: &[u8],
+    data: &[u8],
+    elf: &[u8],
+    opts: &RunOptions,
+) -> Result<(SessionInfo, Vec<PhaseCycles>, Duration)> {
+    execute_with(elf, opts, None, |builder| {
+        builder.write(header)?;
+        for bytes in [model, data] {
+            builder.write(&(bytes.len() as u32))?.write_slice(bytes);
+        }
+        Ok(())
+    })
+}
+
+fn execute_with(
+    elf: &[u8],
+    opts: &RunOptions,
+    profile: Option<&Path>,
+    write_inputs: impl for<'a> FnOnce(&mut ExecutorEnvBuilder<'a>) -> anyhow::Result<()>,
+) -> Result<(SessionInfo, Vec<PhaseCycles>, Duration)> {
+    let mut stderr = Vec::new();
+    let env = build_env(opts, profile, &mut stderr, write_inputs)?;
+
+    // The executor runs the guest and records its journal without running the
+    // STARK prover, which makes it useful for quick iterations.
+    let start_time = Instant::now();
+    let session = default_executor().execute_elf(env, elf);
+    let execution_time = start_time.elapsed();
+    let stderr = String::from_utf8_lossy(&stderr);
+    forward_guest_output(&stderr);
+    let session = session.map_err(Error::Executor)?;
+    Ok((session, phase_cycles(&stderr), execution_time))
+}
+
+/// Writes what the guest wrote to its stderr, less the cycle checkpoints, to
+/// the host's stderr.
+fn forward_guest_output(stderr: &str) {
+    for line in guest_output(stderr) {
+        eprintln!("{}", line);
+    }
+}
+
+pub fn predict<T: Serialize, D: Serialize>(
+    model: &T,
+    data: &D,
+    elf: &[u8],
+    opts: &RunOptions,
+) -> Result<(Receipt, Duration)> {
+    // Cycle checkpoints are collected by the executor run, see `execute`, so
+    // only the rest of the guest's stderr is kept.
+    let mut stderr = Vec::new();
+    let env = build_env(opts, None, &mut stderr, |builder| {
+        builder.write(model)?.write(data)?;
+        Ok(())
+    })?;
+
+    // Obtain the default prover.
+    // Note that for development purposes we do not need to run the prover. To
+    // bypass the prover, use:
+    // ```
+    // RISC0_DEV_MODE=1 cargo run -r
+    // ```
+    // The receipt is then fake and the proving time meaningless, which is
+    // why runs in dev mode are flagged in the report, see [dev_mode].
+    let prover = default_prover();
+
+    // This initiates a session, runs the STARK prover on the resulting exection
+    // trace, and produces a receipt.
+    let start_time = Instant::now();
+    let receipt = prover.prove_elf(env, elf);
+    let proving_time = start_time.elapsed();
+    forward_guest_output(&String::from_utf8_lossy(&stderr));
+    let receipt = receipt.map_err(Error::Prover)?;
+    Ok((receipt, proving_time))
+}
+
+/// Returns whether `RISC0_DEV_MODE` is enabled, in which case the prover
+/// skips proving and produces fake receipts. This follows the values risc0
+/// itself accepts.
+pub fn dev_mode() -> bool {
+    std::env::var("RISC0_DEV_MODE").map_or(false, |value| {
+        matches!(value.to_lowercase().as_str(), "1" | "true" | "yes")
+    })
+}
+
+/// Returns whether the prover runs in this process, so that the peak memory
+/// measured by [crate::memory] includes it. risc0 runs it in the external
+/// `r0vm` process unless the `prove` feature links it in, and `RISC0_PROVER`
+/// picks either explicitly.
+pub fn in_process_prover() -> bool {
+    match std::env::var("RISC0_PROVER") {
+        Ok(prover) if !prover.is_empty() => prover.eq_ignore_ascii_case("local"),
+        _ => cfg!(feature = "prove"),
+    }
+}
+
+/// Compresses a composite receipt into a single succinct receipt with the
+/// recursion prover, which runs on the CPU.
+#[cfg(feature = "prove")]
+pub fn compress(receipt: &Receipt) -> Result<(Receipt, Duration)> {
+    let prover = get_prover_server(&ProverOpts::default()).map_err(Error::Prover)?;
+    let start_time = Instant::now();
+    let succinct_receipt = prover.compress(receipt).map_err(Error::Prover)?;
+    let compression_time = start_time.elapsed();
+    Ok((succinct_receipt, compression_time))
+}
+
+/// The recursion prover is only linked in with the `prove` feature, so
+/// without it receipts cannot be compressed.
+#[cfg(not(feature = "prove"))]
+pub fn compress(_receipt: &Receipt) -> Result<(Receipt, Duration)> {
+    Err(Error::Prover(anyhow::anyhow!(
+        "compressing receipts needs a binary built with the prove feature"
+    )))
+}
+
+/// Verifies `receipt` against `image_id` and returns how long it took.
+pub fn verify(receipt: &Receipt, image_id: [u32; 8]) -> Result<Duration> {
+    let start_time = Instant::now();
+    receipt
+        .verify(image_id)
+        .map_err(|err| Error::Verification(err.to_string()))?;
+    Ok(start_time.elapsed())
+}
+
+/// Decodes a journal into `T`, failing unless `T` accounts for the journal
+/// exactly.
+///
+/// risc0 serde decodes a journal into any type with a prefix of its layout
+/// and ignores trailing commits. Re-encoding the decoded value and comparing
+/// it with the journal catches those. It cannot tell integer types of the
+/// same word apart: `-1` committed as an `i32` decodes to `u32::MAX` and
+/// encodes back to the same word. That is ruled out at compile time instead,
+/// by the model and label aliases the guests and the host share.
+pub fn decode_journal<T: DeserializeOwned + Serialize>(journal: &Journal) -> Result<T> {
+    let value: T = journal
+        .decode()
+        .map_err(|err| Error::Journal(err.to_string()))?;
+    let words = risc0_zkvm::serde::to_vec(&value).map_err(|err| Error::Journal(err.to_string()))?;
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    if bytes != journal.bytes {
+        return Err(Error::Journal(format!(
+            "journal does not match the layout of {}",
+            std::any::type_name::<T>()
+        )));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn model_file_overrides_shipped_artifact() {
+        let shipped = RunOptions::default().artifact_paths("linear_regression");
+        let bytes = read_artifact_bytes(&shipped.model).unwrap();
+
+        // The same model written as raw rmp bytes, outside of res/.
+        let dir = tempfile::tempdir().unwrap();
+        let model_file = dir.path().join("model.rmp");
+        fs::write(&model_file, &bytes).unwrap();
+
+        let opts = RunOptions {
+            model_file: Some(model_file.clone()),
+            ..Default::default()
+        };
+        let paths = opts.artifact_paths("linear_regression");
+        assert_eq!(paths.model, model_file);
+        assert_eq!(paths.data, shipped.data);
+        assert_eq!(read_artifact_bytes(&paths.model).unwrap(), bytes);
+    }
+
+    #[test]
+    fn journal_of_another_type_is_rejected() {
+        let words = risc0_zkvm::serde::to_vec(&vec![300u32, 7]).unwrap();
+        let journal = Journal::new(words.iter().flat_map(|word| word.to_le_bytes()).collect());
+        assert_eq!(decode_journal::<Vec<u32>>(&journal).unwrap(), vec![300, 7]);
+        // 300 does not fit into a u8
+        assert!(matches!(
+            decode_journal::<Vec<u8>>(&journal),
+            Err(Error::Journal(_))
+        ));
+        // Trailing words are not part of a single u32
+        assert!(matches!(
+            decode_journal::<u32>(&journal),
+            Err(Error::Journal(_))
+        ));
+    }
+
+    #[test]
+    fn missing_artifact_is_reported() {
+        let opts = RunOptions {
+            workdir: PathBuf::from("does-not-exist"),
+            ..Default::default()
+        };
+        let paths = opts.artifact_paths("linear_regression");
+        let err = read_artifact_bytes(&paths.model).unwrap_err();
+        assert!(matches!(err, Error::MissingArtifact { .. }));
+        assert_eq!(err.exit_code(), 4);
+    }
+}
@@ -0,0 +1,29 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Host side of the risc0 zkml benchmarks: the registry of benchmarked
+//! models and the code to execute, prove and verify their guests.
+
+pub mod batch;
+pub mod error;
+pub mod fingerprint;
+pub mod fixed;
+pub mod host;
+pub mod memory;
+pub mod models;
+pub mod receipt;
+pub mod report;
+pub mod sweep;
+pub mod synthetic;
+pub mod wire;
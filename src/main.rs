@@ -12,258 +12,554 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use clap::{App, Arg};
-use risc0_zkvm::{default_prover, ExecutorEnv};
-use serde::Serialize;
-use serde_json;
-use smartcore::{
-    ensemble::{random_forest_classifier::*, random_forest_regressor::*},
-    linalg::basic::matrix::DenseMatrix,
-    linear::linear_regression::LinearRegression,
-    svm::svc::SVC,
+use clap::{App, Arg, ArgGroup, ArgMatches, ErrorKind};
+use smartcore_ml_common::{Disclosure, Visibility};
+use std::path::{Path, PathBuf};
+use zkml_benchmarks::error::{Error, Result};
+use zkml_benchmarks::fingerprint::Fingerprint;
+use zkml_benchmarks::host::RunOptions;
+use zkml_benchmarks::models::{self, DynBenchModel, Guest, MODELS};
+use zkml_benchmarks::receipt::SavedReceipt;
+use zkml_benchmarks::report::{self, ReportFormat};
+use zkml_benchmarks::sweep::{
+    print_sweep, row_counts, segment_po2, segment_po2s, sweep, thread_count, thread_counts,
 };
-use smartcore_ml_methods::LINEAR_REGRESSION_ELF;
-use smartcore_ml_methods::RANDOM_FOREST_ELF;
-use smartcore_ml_methods::SVM_CLASSIFICATION_ELF;
-use smartcore_ml_methods::TE_REGRESSION_ELF;
-use std::fs;
-use std::time::Instant;
-
-// The serialized trained model and input data are embedded from files
-// corresponding paths listed below. Alternatively, the model can be trained in
-// the host and/or data can be manually inputted as a smartcore DenseMatrix. If
-// this approach is desired, be sure to import the corresponding SmartCore
-// modules and serialize the model and data to byte arrays before transfer to
-// the guest.
+use zkml_benchmarks::wire::{compare_formats, print_formats};
 
 fn main() {
+    let model_names: Vec<&str> = MODELS.iter().map(|model| model.name()).collect();
+    let model_help = format!("Specifies the model ({})", model_names.join(", "));
+    let model_arg = Arg::new("model")
+        .short('m')
+        .long("model")
+        .takes_value(true)
+        .required(true)
+        .help(model_help.as_str());
+    let report_arg = Arg::new("report")
+        .long("report")
+        .takes_value(true)
+        .possible_values(["text", "json"])
+        .default_value("text")
+        .help("Output format of the run report");
+    let artifact_args = [
+        Arg::new("model-file")
+            .long("model-file")
+            .takes_value(true)
+            .requires("model")
+            .help("Reads the model from this file instead of res/ml-model (.json byte array or raw rmp)"),
+        Arg::new("data-file")
+            .long("data-file")
+            .takes_value(true)
+            .requires("model")
+            .help("Reads the input data from this file instead of res/input-data (.json byte array or raw rmp)"),
+        Arg::new("batch")
+            .long("batch")
+            .takes_value(true)
+            .requires("model")
+            .conflicts_with("data-file")
+            .help("Predicts every row of this CSV or NPY file instead of the input data artifact"),
+        Arg::new("workdir")
+            .long("workdir")
+            .takes_value(true)
+            .help("Directory containing res/ (defaults to the current directory)"),
+    ];
+    let segment_po2_arg = Arg::new("segment-po2")
+        .long("segment-po2")
+        .takes_value(true)
+        .validator(segment_po2)
+        .help("Segment size limit as a power of two (defaults to the risc0 default)");
+    let visibility_args = [
+        Arg::new("model-visibility")
+            .long("model-visibility")
+            .takes_value(true)
+            .possible_values(["public", "private"])
+            .default_value("private")
+            .help("Commits the model to the journal verbatim (public) or as its digest (private)"),
+        Arg::new("input-visibility")
+            .long("input-visibility")
+            .takes_value(true)
+            .possible_values(["public", "private"])
+            .default_value("private")
+            .help("Commits the input data to the journal verbatim (public) or as its digest (private)"),
+    ];
+    let generic_arg = Arg::new("generic")
+        .long("generic")
+        .help("Runs the generic inference guest instead of the model's own guest");
+    let rows_arg = Arg::new("rows")
+        .long("rows")
+        .takes_value(true)
+        .requires("model")
+        .validator(|value| match value.parse::<usize>() {
+            Ok(rows) if rows > 0 => Ok(()),
+            _ => Err("expected a positive number of rows"),
+        })
+        .help("Predicts this many synthetic rows shaped like the input data");
+    let threads_arg = Arg::new("threads")
+        .long("threads")
+        .takes_value(true)
+        .validator(thread_count)
+        .help("Number of threads the executor and prover run on (defaults to one per core)");
+
     let matches = App::new("Model Prover")
         .version("1.0")
         .author("Your Name")
-        .about("Proves models using RISC Zero")
-        .arg(
-            Arg::with_name("model")
-                .short('m')
-                .long("model")
-                .takes_value(true)
-                .help("Specifies the model to prove (linear_regression or random_forest)"),
+        .about("Executes, proves and verifies models using RISC Zero")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            App::new("execute")
+                .about(
+                    "Runs the guest in the executor without proving and reports its cycle counts",
+                )
+                .arg(
+                    model_arg.clone().required(false).help(
+                        "Specifies the model; every registered model is executed when omitted",
+                    ),
+                )
+                .arg(report_arg.clone())
+                .arg(segment_po2_arg.clone())
+                .arg(threads_arg.clone())
+                .arg(rows_arg.clone())
+                .args(visibility_args.clone())
+                .arg(generic_arg.clone())
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .takes_value(true)
+                        .value_name("DIR")
+                        .help("Writes a pprof profile of each guest to DIR/<model>.pb"),
+                )
+                .args(artifact_args.clone()),
+        )
+        .subcommand(
+            App::new("prove")
+                .about("Proves inference of the model on its input data")
+                .arg(model_arg.clone())
+                .arg(report_arg.clone())
+                .arg(segment_po2_arg.clone())
+                .arg(threads_arg.clone())
+                .arg(rows_arg.clone())
+                .args(visibility_args.clone())
+                .arg(generic_arg.clone())
+                .arg(
+                    Arg::new("runs")
+                        .long("runs")
+                        .takes_value(true)
+                        .default_value("1")
+                        .validator(|value| match value.parse::<usize>() {
+                            Ok(runs) if runs > 0 => Ok(()),
+                            _ => Err("expected a positive number of runs"),
+                        })
+                        .help("Number of measured proofs"),
+                )
+                .arg(
+                    Arg::new("warmup")
+                        .long("warmup")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Number of proofs made before the measured ones"),
+                )
+                .arg(
+                    Arg::new("succinct")
+                        .long("succinct")
+                        .help("Compresses the receipt into a succinct receipt and reports both (needs the prove feature)"),
+                )
+                .args(artifact_args.clone())
+                .arg(
+                    Arg::new("receipt")
+                        .short('r')
+                        .long("receipt")
+                        .takes_value(true)
+                        .help("Writes the receipt to the given file"),
+                ),
+        )
+        .subcommand(
+            App::new("fixed-point")
+                .about("Proves the model in the fixed-point guest and compares its predictions with f64")
+                .arg(model_arg.clone())
+                .arg(report_arg.clone())
+                .arg(
+                    Arg::new("scale")
+                        .long("scale")
+                        .takes_value(true)
+                        .value_name("BITS")
+                        .default_value("16")
+                        .validator(|value| match value.parse::<u32>() {
+                            Ok(bits) if bits <= 32 => Ok(()),
+                            _ => Err("expected a number of bits from 0 to 32"),
+                        })
+                        .help("Number of fractional bits of the fixed-point values"),
+                )
+                .arg(
+                    Arg::new("execute")
+                        .long("execute")
+                        .help("Runs the executor only instead of proving"),
+                )
+                .arg(segment_po2_arg.clone())
+                .arg(threads_arg.clone())
+                .arg(rows_arg.clone())
+                .args(visibility_args.clone())
+                .args(artifact_args.clone()),
+        )
+        .subcommand(
+            App::new("formats")
+                .about("Executes the model with its inputs decoded by the guest from each wire format and compares their cycles")
+                .arg(
+                    model_arg.clone().required(false).help(
+                        "Specifies the model; every registered model is compared when omitted",
+                    ),
+                )
+                .arg(report_arg.clone())
+                .arg(segment_po2_arg)
+                .arg(threads_arg)
+                .arg(rows_arg)
+                .args(visibility_args)
+                .args(artifact_args.clone()),
+        )
+        .subcommand(
+            App::new("sweep")
+                .about("Runs a model once per value of a setting, each in a fresh process")
+                .arg(
+                    model_arg.clone().required(false).help(
+                        "Specifies the model; every registered model is swept when omitted",
+                    ),
+                )
+                .arg(report_arg)
+                .arg(
+                    Arg::new("po2")
+                        .long("po2")
+                        .takes_value(true)
+                        .validator(segment_po2s)
+                        .help("Segment size limits to sweep, e.g. 16-20 or 16,18,20"),
+                )
+                .arg(
+                    Arg::new("threads")
+                        .long("threads")
+                        .takes_value(true)
+                        .validator(thread_counts)
+                        .help("Thread counts to sweep, e.g. 1,2,4,8, or auto for powers of two up to the core count"),
+                )
+                .arg(
+                    Arg::new("rows")
+                        .long("rows")
+                        .takes_value(true)
+                        .validator(row_counts)
+                        .help("Synthetic input sizes to sweep, e.g. 1,10,100,1000"),
+                )
+                .group(
+                    ArgGroup::new("setting")
+                        .args(&["po2", "threads", "rows"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("execute")
+                        .long("execute")
+                        .help("Runs the executor only instead of proving"),
+                )
+                .arg(generic_arg.clone())
+                .args(artifact_args.clone()),
+        )
+        .subcommand(
+            App::new("verify")
+                .about(
+                    "Verifies a receipt written by `prove --receipt` against the model's image ID",
+                )
+                .arg(
+                    model_arg
+                        .clone()
+                        .required(false)
+                        .help("Overrides the model recorded in the receipt file"),
+                )
+                .arg(
+                    Arg::new("receipt")
+                        .short('r')
+                        .long("receipt")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Receipt file to verify"),
+                )
+                .arg(
+                    Arg::new("model-file")
+                        .long("model-file")
+                        .takes_value(true)
+                        .help("Checks that the receipt was produced with this model artifact (.json byte array or raw rmp)"),
+                ),
+        )
+        .subcommand(
+            App::new("fingerprint")
+                .about("Prints the machine, toolchain and build this binary runs on as JSON"),
+        )
+        .subcommand(
+            App::new("inspect")
+                .about("Describes the model and input data artifacts")
+                .arg(model_arg)
+                .arg(generic_arg)
+                .args(artifact_args),
         )
         .get_matches();
 
-    // Determine which model to prove based on user input
-    let model_type = matches.value_of("model").unwrap_or("default_model");
+    let (command, args) = matches.subcommand().unwrap();
+    if let Err(err) = run(command, args) {
+        eprintln!("Error: {}", err);
+        std::process::exit(err.exit_code());
+    }
+}
 
-    let output = match model_type {
-        "linear_regressions" => {
-            let model_linear_regression =
-                &fs::read_to_string("./res/ml-model/linear_regression_model_bytes.json").unwrap();
-            let data_linear_regression =
-                &fs::read_to_string("./res/input-data/linear_regression_data_bytes.json").unwrap();
-            // Convert the model and input data from JSON into byte arrays.
-            let model_bytes: Vec<u8> = serde_json::from_str(model_linear_regression).unwrap();
-            let data_bytes: Vec<u8> = serde_json::from_str(data_linear_regression).unwrap();
+fn run(command: &str, args: &ArgMatches) -> Result<()> {
+    // The only subcommand that is not about a model.
+    if command == "fingerprint" {
+        let fingerprint = Fingerprint::collect();
+        println!("{}", serde_json::to_string_pretty(&fingerprint).unwrap());
+        return Ok(());
+    }
 
-            // Deserialize the data from rmp into native rust types.
-            type Model = LinearRegression<f64, u32, DenseMatrix<f64>, Vec<u32>>;
-            let model: Model = rmp_serde::from_slice(&model_bytes)
-                .expect("model failed to deserialize byte array");
-            let data: DenseMatrix<f64> =
-                rmp_serde::from_slice(&data_bytes).expect("data failed to deserialize byte array");
-            Ok(predict(&model, data, LINEAR_REGRESSION_ELF))
-        }
-        "random_forests" => {
-            let model_random_forest =
-                &fs::read_to_string("./res/ml-model/random_forest_model_bytes.json").unwrap();
-            let data_random_forest =
-                &fs::read_to_string("./res/input-data/random_forest_data_bytes.json").unwrap();
-            // Convert the model and input data from JSON into byte arrays.
-            let model_bytes: Vec<u8> = serde_json::from_str(model_random_forest).unwrap();
-            let data_bytes: Vec<u8> = serde_json::from_str(data_random_forest).unwrap();
+    // Determine which models to use based on user input. `execute`, `sweep`
+    // and `formats` run every registered model when none is given.
+    let selected: Vec<&dyn DynBenchModel> = match args.value_of("model") {
+        Some(model_type) => vec![models::find(model_type)?],
+        None => MODELS.to_vec(),
+    };
+    let model = selected[0];
 
-            // Deserialize the data from rmp into native rust types.
-            type Model = RandomForestClassifier<f64, u8, DenseMatrix<f64>, Vec<u8>>;
-            let model: Model = rmp_serde::from_slice(&model_bytes)
-                .expect("model failed to deserialize byte array");
-            let data: DenseMatrix<f64> =
-                rmp_serde::from_slice(&data_bytes).expect("data failed to deserialize byte array");
-            Ok(predict(&model, data, RANDOM_FOREST_ELF))
+    match command {
+        "execute" => {
+            configure_threads(args)?;
+            let format = ReportFormat::from_arg(args.value_of("report").unwrap());
+            let opts = RunOptions {
+                guest: guest(args),
+                profile_dir: args.value_of("profile").map(PathBuf::from),
+                ..run_options(args, format)
+            };
+            if let Some(dir) = &opts.profile_dir {
+                std::fs::create_dir_all(dir).map_err(|source| Error::Profile {
+                    path: dir.clone(),
+                    source,
+                })?;
+            }
+            let reports = selected
+                .iter()
+                .map(|model| model.execute(&opts))
+                .collect::<Result<Vec<_>>>()?;
+            report::print_all(&reports, format);
         }
-        "svm_classifications" => {
-            let model_svm_classification =
-                &fs::read_to_string("./res/ml-model/svm_classification_model_bytes.json").unwrap();
-            let data_svm_classification =
-                &fs::read_to_string("./res/input-data/svm_classification_data_bytes.json").unwrap();
-            // Convert the model and input data from JSON into byte arrays.
-            let model_bytes: Vec<u8> = serde_json::from_str(model_svm_classification).unwrap();
-            let data_bytes: Vec<u8> = serde_json::from_str(data_svm_classification).unwrap();
-
-            // Deserialize the data from rmp into native rust types.
-            let model: SVC<f64, i32, DenseMatrix<f64>, Vec<i32>> =
-                rmp_serde::from_slice(&model_bytes)
-                    .expect("model failed to deserialize byte array");
-            let data: DenseMatrix<f64> =
-                rmp_serde::from_slice(&data_bytes).expect("data failed to deserialize byte array");
-            Ok(predict(&model, data, SVM_CLASSIFICATION_ELF))
+        "prove" => {
+            // Compressing needs the recursion prover, which only the `prove`
+            // feature links in.
+            if args.is_present("succinct") && !cfg!(feature = "prove") {
+                clap::Error::raw(
+                    ErrorKind::ArgumentConflict,
+                    "--succinct needs a binary built with the prove feature\n",
+                )
+                .exit();
+            }
+            configure_threads(args)?;
+            let format = ReportFormat::from_arg(args.value_of("report").unwrap());
+            let opts = RunOptions {
+                guest: guest(args),
+                runs: args.value_of_t_or_exit("runs"),
+                warmup: args.value_of_t_or_exit("warmup"),
+                succinct: args.is_present("succinct"),
+                ..run_options(args, format)
+            };
+            let (report, receipt) = model.prove(&opts)?;
+            if report.dev_mode {
+                eprintln!("Warning: RISC0_DEV_MODE is enabled, the receipt is fake");
+            }
+            report.print(format);
+            if let Some(path) = args.value_of("receipt") {
+                SavedReceipt::new(model.name(), opts.guest, receipt).save(Path::new(path))?;
+                eprintln!("Receipt written to: {}", path);
+            }
         }
-        "te_regressions" => {
-            let model_te_regression =
-                &fs::read_to_string("./res/ml-model/te_regression_model_bytes.json").unwrap();
-            let data_te_regression =
-                &fs::read_to_string("./res/input-data/te_regression_data_bytes.json").unwrap();
-            // Convert the model and input data from JSON into byte arrays.
-            let model_bytes: Vec<u8> = serde_json::from_str(model_te_regression).unwrap();
-            let data_bytes: Vec<u8> = serde_json::from_str(data_te_regression).unwrap();
-
-            // Deserialize the data from rmp into native rust types.
-            let model: RandomForestRegressor<f64, i32, DenseMatrix<f64>, Vec<i32>> =
-                rmp_serde::from_slice(&model_bytes)
-                    .expect("model failed to deserialize byte array");
-            let data: DenseMatrix<f64> =
-                rmp_serde::from_slice(&data_bytes).expect("data failed to deserialize byte array");
-            Ok(predict(&model, data, TE_REGRESSION_ELF))
+        "fixed-point" => {
+            configure_threads(args)?;
+            let format = ReportFormat::from_arg(args.value_of("report").unwrap());
+            let execute_only = args.is_present("execute");
+            let report = model.fixed_point(
+                &run_options(args, format),
+                args.value_of_t_or_exit("scale"),
+                execute_only,
+            )?;
+            if report.dev_mode && !execute_only {
+                eprintln!("Warning: RISC0_DEV_MODE is enabled, the receipt is fake");
+            }
+            report.print(format);
         }
-        _ => {
-            // return an error if the model type is not recognized
-            Err("Model type not recognized")
+        "formats" => {
+            configure_threads(args)?;
+            let format = ReportFormat::from_arg(args.value_of("report").unwrap());
+            let opts = run_options(args, format);
+            let mut points = Vec::new();
+            for model in &selected {
+                points.extend(compare_formats(*model, &opts)?);
+            }
+            print_formats(&points, format);
         }
-    };
-    let output = output.unwrap();
-    println!("Prediction recorded in journal is: {:?}", &output.0);
-    println!("Proving time: {:?}", &output.1);
+        "sweep" => {
+            let format = ReportFormat::from_arg(args.value_of("report").unwrap());
+            let command = if args.is_present("execute") {
+                "execute"
+            } else {
+                "prove"
+            };
+            let mut child_args: Vec<String> = [command, "--report", "json"]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+            for name in ARTIFACT_ARGS {
+                if let Some(value) = args.value_of(name) {
+                    child_args.push(format!("--{}", name));
+                    child_args.push(value.to_string());
+                }
+            }
+            if args.is_present("generic") {
+                child_args.push("--generic".to_string());
+            }
+            let (name, flag, values) = if let Some(po2) = args.value_of("po2") {
+                ("po2", "--segment-po2", segment_po2s(po2))
+            } else if let Some(rows) = args.value_of("rows") {
+                ("rows", "--rows", row_counts(rows))
+            } else {
+                let threads = args.value_of("threads").unwrap();
+                ("threads", "--threads", thread_counts(threads))
+            };
+            let values = values.unwrap();
+            let mut points = Vec::new();
+            for model in &selected {
+                let mut model_args = child_args.clone();
+                model_args.extend(["--model".to_string(), model.name().to_string()]);
+                points.extend(sweep(&model_args, flag, &values)?);
+            }
+            print_sweep(name, &points, format);
+        }
+        "verify" => {
+            let saved_receipt = SavedReceipt::load(Path::new(args.value_of("receipt").unwrap()))?;
+            // A saved receipt records the model that produced it, which is
+            // used unless another model is given.
+            let model = match args.value_of("model") {
+                Some(_) => model,
+                None => models::find(&saved_receipt.model)?,
+            };
+            if saved_receipt.model != model.name() {
+                eprintln!(
+                    "Warning: receipt was produced for {}, verifying as {}",
+                    saved_receipt.model,
+                    model.name()
+                );
+            }
+            let verification = model.verify(&saved_receipt.receipt, saved_receipt.guest);
+            println!("Image ID: {}", verification.image_id);
+            match verification.result {
+                Ok(()) => println!("Verification: passed"),
+                Err(err) => {
+                    println!("Verification: failed ({})", err);
+                    return Err(Error::Verification(err));
+                }
+            }
+            let journal = model.read_journal(&saved_receipt.receipt, saved_receipt.guest)?;
+            println!("Prediction recorded in journal is: {}", journal.predictions);
+            println!(
+                "Model: {:?}, digest {}",
+                journal.visibility.model, journal.model_digest
+            );
+            println!(
+                "Input: {:?}, digest {}",
+                journal.visibility.input, journal.input_digest
+            );
+            // The image ID only says which guest ran, the model digest says
+            // which model it ran.
+            if let Some(path) = args.value_of("model-file") {
+                let expected = model.model_digest(Path::new(path))?;
+                if journal.model_digest != expected {
+                    println!("Model check: failed");
+                    return Err(Error::Verification(format!(
+                        "receipt was produced with model {}, but {} has digest {}",
+                        journal.model_digest, path, expected
+                    )));
+                }
+                println!("Model check: passed");
+            }
+        }
+        "inspect" => {
+            let opts = RunOptions {
+                guest: guest(args),
+                ..artifact_options(args)
+            };
+            model.inspect(&opts)?.print()
+        }
+        _ => unreachable!("subcommand {} is not handled", command),
+    }
+    Ok(())
 }
 
-fn predict<T: Serialize>(
-    model: &T,
-    data: DenseMatrix<f64>,
-    exec_env: &[u8],
-) -> (Vec<u32>, std::time::Duration) {
-    let env = ExecutorEnv::builder()
-        .write(model)
-        .expect("model failed to serialize")
-        .write(&data)
-        .expect("data failed to serialize")
-        .build()
-        .unwrap();
-
-    // Obtain the default prover.
-    // Note that for development purposes we do not need to run the prover. To
-    // bypass the prover, use:
-    // ```
-    // RISC0_DEV_MODE=1 cargo run -r
-    // ```
-    let prover = default_prover();
-
-    // This initiates a session, runs the STARK prover on the resulting exection
-    // trace, and produces a receipt.
-    let start_time = Instant::now();
-    let receipt = prover.prove_elf(env, exec_env).unwrap();
-    let proving_time = start_time.elapsed();
-    // We read the result that the guest code committed to the journal. The
-    // receipt can also be serialized and sent to a verifier.
-    (receipt.journal.decode().unwrap(), proving_time)
+/// Sizes the thread pool the executor and prover parallelize over when
+/// `--threads` is given. An in-process prover uses the global pool of this
+/// process; `r0vm` sizes its own from `RAYON_NUM_THREADS`, which it inherits.
+fn configure_threads(args: &ArgMatches) -> Result<()> {
+    if args.is_present("threads") {
+        let threads: usize = args.value_of_t_or_exit("threads");
+        std::env::set_var("RAYON_NUM_THREADS", threads.to_string());
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|err| Error::Threads(err.to_string()))?;
+    }
+    Ok(())
 }
 
-#[cfg(test)]
-mod test {
-    use std::env;
-    use std::fs;
+/// Arguments selecting the artifacts of a run, passed on to the runs of a
+/// sweep.
+const ARTIFACT_ARGS: [&str; 4] = ["model-file", "data-file", "batch", "workdir"];
 
-    use smartcore::ensemble::random_forest_regressor::RandomForestRegressor;
-    use smartcore::{
-        ensemble::random_forest_classifier::*, linalg::basic::matrix::DenseMatrix,
-        linear::linear_regression::LinearRegression, svm::svc::SVC,
-    };
-    use smartcore_ml_methods::LINEAR_REGRESSION_ELF;
-    use smartcore_ml_methods::RANDOM_FOREST_ELF;
-    use smartcore_ml_methods::SVM_CLASSIFICATION_ELF;
-    use smartcore_ml_methods::TE_REGRESSION_ELF;
-
-    #[test]
-    fn linear_regression() {
-        let model_linear_regression =
-            &fs::read_to_string("./res/ml-model/linear_regression_model_bytes.json").unwrap();
-        let data_linear_regression =
-            &fs::read_to_string("./res/input-data/linear_regression_data_bytes.json").unwrap();
-        const EXPECTED: &[u32] = &[3];
-        // Convert the model and input data from JSON into byte arrays.
-        let model_bytes: Vec<u8> = serde_json::from_str(model_linear_regression).unwrap();
-        let data_bytes: Vec<u8> = serde_json::from_str(data_linear_regression).unwrap();
-
-        // Deserialize the data from rmp into native rust types.
-        type Model = LinearRegression<f64, u32, DenseMatrix<f64>, Vec<u32>>;
-        let model: Model =
-            rmp_serde::from_slice(&model_bytes).expect("model failed to deserialize byte array");
-        let data: DenseMatrix<f64> =
-            rmp_serde::from_slice(&data_bytes).expect("data failed to deserialize byte array");
-        let result = super::predict(&model, data, LINEAR_REGRESSION_ELF);
-        assert_eq!(EXPECTED, result.0);
+/// The guest chosen with `--generic`, for the subcommands that take it.
+fn guest(args: &ArgMatches) -> Guest {
+    if args.is_present("generic") {
+        Guest::Generic
+    } else {
+        Guest::Dedicated
     }
-    #[test]
-    fn random_forest() {
-        println!(
-            "Current working directory: {:?}",
-            env::current_dir().unwrap()
-        );
-        let model_random_forest =
-            &fs::read_to_string("./res/ml-model/random_forest_model_bytes.json").unwrap();
-        let data_random_forest =
-            &fs::read_to_string("./res/input-data/random_forest_data_bytes.json").unwrap();
-        const EXPECTED: &[u8] = &[0];
-        // Convert the model and input data from JSON into byte arrays.
-        let model_bytes: Vec<u8> = serde_json::from_str(model_random_forest).unwrap();
-        let data_bytes: Vec<u8> = serde_json::from_str(data_random_forest).unwrap();
+}
 
-        // Deserialize the data from rmp into native rust types.
-        type Model = RandomForestClassifier<f64, u8, DenseMatrix<f64>, Vec<u8>>;
-        let model: Model =
-            rmp_serde::from_slice(&model_bytes).expect("model failed to deserialize byte array");
-        let data: DenseMatrix<f64> =
-            rmp_serde::from_slice(&data_bytes).expect("data failed to deserialize byte array");
-        let result = super::predict(&model, data, RANDOM_FOREST_ELF);
-        // convert result.0 to a Vec<u8>
-        let result: Vec<u8> = result.0.iter().map(|x| *x as u8).collect();
-        assert_eq!(EXPECTED, result);
+/// Options of the subcommands that take [ARTIFACT_ARGS].
+fn artifact_options(args: &ArgMatches) -> RunOptions {
+    RunOptions {
+        workdir: args
+            .value_of("workdir")
+            .map(PathBuf::from)
+            .unwrap_or_default(),
+        model_file: args.value_of("model-file").map(PathBuf::from),
+        data_file: args.value_of("data-file").map(PathBuf::from),
+        batch_file: args.value_of("batch").map(PathBuf::from),
+        ..Default::default()
     }
-    #[test]
-    fn svm_classification() {
-        let model_svm_classification =
-            &fs::read_to_string("./res/ml-model/svm_classification_model_bytes.json").unwrap();
-        let data_svm_classification =
-            &fs::read_to_string("./res/input-data/svm_classification_data_bytes.json").unwrap();
-        const EXPECTED: &[i32] = &[
-            -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
-        ];
-
-        let model_bytes: Vec<u8> = serde_json::from_str(model_svm_classification).unwrap();
-        let data_bytes: Vec<u8> = serde_json::from_str(data_svm_classification).unwrap();
+}
 
-        // Deserialize the data from rmp into native rust types.
-        let model: SVC<f64, i32, DenseMatrix<f64>, Vec<i32>> =
-            rmp_serde::from_slice(&model_bytes).expect("model failed to deserialize byte array");
-        let data: DenseMatrix<f64> =
-            rmp_serde::from_slice(&data_bytes).expect("data failed to deserialize byte array");
-        let result = super::predict(&model, data, SVM_CLASSIFICATION_ELF);
-        // convert result.0 to a Vec<i32>
-        let result: Vec<i32> = result.0.iter().map(|x| *x as i32).collect();
-        assert_eq!(EXPECTED, result);
+/// Options of `execute`, `prove`, `fixed-point` and `formats`, which also
+/// run the guest.
+fn run_options(args: &ArgMatches, format: ReportFormat) -> RunOptions {
+    RunOptions {
+        guest_stdout_to_stderr: format == ReportFormat::Json,
+        visibility: Visibility {
+            model: disclosure(args.value_of("model-visibility").unwrap()),
+            input: disclosure(args.value_of("input-visibility").unwrap()),
+        },
+        synthetic_rows: if args.is_present("rows") {
+            Some(args.value_of_t_or_exit("rows"))
+        } else {
+            None
+        },
+        segment_limit_po2: if args.is_present("segment-po2") {
+            Some(args.value_of_t_or_exit("segment-po2"))
+        } else {
+            None
+        },
+        ..artifact_options(args)
     }
-    #[test]
-    fn te_regression() {
-        let model_te_regression =
-            &fs::read_to_string("./res/ml-model/te_regression_model_bytes.json").unwrap();
-        let data_te_regression =
-            &fs::read_to_string("./res/input-data/te_regression_data_bytes.json").unwrap();
-        const EXPECTED: &[u32] = &[11];
-
-        let model_bytes: Vec<u8> = serde_json::from_str(model_te_regression).unwrap();
-        let data_bytes: Vec<u8> = serde_json::from_str(data_te_regression).unwrap();
+}
 
-        // Deserialize the data from rmp into native rust types.
-        let model: RandomForestRegressor<f64, u32, DenseMatrix<f64>, Vec<u32>> =
-            rmp_serde::from_slice(&model_bytes).expect("model failed to deserialize byte array");
-        let data: DenseMatrix<f64> =
-            rmp_serde::from_slice(&data_bytes).expect("data failed to deserialize byte array");
-        let result = super::predict(&model, data, TE_REGRESSION_ELF);
-        assert_eq!(EXPECTED, result.0);
+fn disclosure(value: &str) -> Disclosure {
+    match value {
+        "public" => Disclosure::Public,
+        _ => Disclosure::Private,
     }
 }
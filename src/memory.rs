@@ -0,0 +1,100 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Peak memory of the host process, measured per phase of a run.
+//!
+//! On Linux the peak resident set size is read from the `VmHWM` line of
+//! `/proc/self/status` and reset between phases through
+//! `/proc/self/clear_refs`, so that each phase reports its own peak. Other
+//! unix systems only report the peak of the whole process, through
+//! `getrusage`.
+//!
+//! Only this process is measured. A guest run in the external `r0vm`
+//! process, as risc0 does without the `prove` feature, is left out, see
+//! [crate::host::in_process_prover].
+
+use std::fs;
+
+/// Returns the peak resident set size of this process in kilobytes, since it
+/// started or since the last successful [reset_peak_rss].
+pub fn peak_rss_kb() -> Option<u64> {
+    vm_hwm_kb().or_else(max_rss_kb)
+}
+
+/// Resets the peak resident set size to the current one. Returns `false`
+/// where this is not supported, in which case [peak_rss_kb] keeps reporting
+/// the peak of the whole process.
+pub fn reset_peak_rss() -> bool {
+    // Writing 5 resets the "high water mark" counters, see proc(5).
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// Runs `f` and returns the peak resident set size reached while it ran, or
+/// `None` when the peak could not be reset before running it.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<u64>) {
+    let reset = reset_peak_rss();
+    let value = f();
+    (value, if reset { peak_rss_kb() } else { None })
+}
+
+fn vm_hwm_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[cfg(unix)]
+fn max_rss_kb() -> Option<u64> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: getrusage only writes to the struct it is given, and the struct
+    // is fully initialized when it succeeds.
+    let usage = unsafe {
+        if libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) != 0 {
+            return None;
+        }
+        usage.assume_init()
+    };
+    let max_rss = usage.ru_maxrss as u64;
+    // macOS reports bytes, other systems kilobytes.
+    Some(if cfg!(target_os = "macos") {
+        max_rss / 1024
+    } else {
+        max_rss
+    })
+}
+
+#[cfg(not(unix))]
+fn max_rss_kb() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn phases_report_their_own_peak() {
+        let (_, large) = measure(|| {
+            // Touch every page so that it is resident.
+            let buffer = vec![1u8; 256 << 20];
+            buffer.iter().map(|&byte| byte as u64).sum::<u64>()
+        });
+        let (_, small) = measure(|| ());
+        let (large, small) = (large.unwrap(), small.unwrap());
+        assert!(large > small + (128 << 10), "{}kb vs {}kb", large, small);
+    }
+}
@@ -0,0 +1,728 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The models benchmarked by this crate.
+//!
+//! Each model is a unit struct implementing [BenchModel], and is listed once
+//! in [MODELS]. Adding a model means adding its guest under `methods/`, its
+//! artifacts under `res/`, a [BenchModel] impl and an entry in [MODELS]. The
+//! generic guests also need its model type in `smartcore_ml_common`: an
+//! alias, an `AnyModel` variant and a `ModelKind`, which `raw_bytes` decodes
+//! and `predict_and_commit` dispatches on.
+
+use risc0_zkvm::{sha::Digest, Journal, Receipt, SessionInfo};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use smartcore::{
+    api::Predictor,
+    linalg::basic::{arrays::Array, matrix::DenseMatrix},
+};
+use smartcore_ml_common::{
+    digest, fixed::PlainModel, Disclosed, Disclosure, LinearRegressionLabel, LinearRegressionModel,
+    ModelKind, PhaseCycles, Prediction, RandomForestLabel, RandomForestModel,
+    SvmClassificationLabel, SvmClassificationModel, TeRegressionLabel, TeRegressionModel,
+    Visibility, WireFormat,
+};
+use smartcore_ml_methods::RAW_BYTES_ELF;
+use smartcore_ml_methods::{FIXED_POINT_ELF, FIXED_POINT_ID};
+use smartcore_ml_methods::{INFERENCE_ELF, INFERENCE_ID};
+use smartcore_ml_methods::{LINEAR_REGRESSION_ELF, LINEAR_REGRESSION_ID};
+use smartcore_ml_methods::{RANDOM_FOREST_ELF, RANDOM_FOREST_ID};
+use smartcore_ml_methods::{SVM_CLASSIFICATION_ELF, SVM_CLASSIFICATION_ID};
+use smartcore_ml_methods::{TE_REGRESSION_ELF, TE_REGRESSION_ID};
+use std::fmt::Debug;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::fixed;
+use crate::host::{self, decode_journal, load_inputs, load_model, read_artifact_bytes, RunOptions};
+use crate::memory;
+use crate::receipt::{Verification, VerifiedJournal};
+use crate::report::{Inspection, RunReport, WireFormatReport};
+use crate::wire;
+
+/// A model together with the guest that runs its inference.
+pub trait BenchModel {
+    /// Name used on the command line and as the key in `benchmarks.json`.
+    const NAME: &'static str;
+    /// Prefix of the artifact files under `./res/ml-model` and
+    /// `./res/input-data`.
+    const ARTIFACT: &'static str;
+    const ELF: &'static [u8];
+    const IMAGE_ID: [u32; 8];
+    /// Tag of the model for the generic guest, see [Guest::Generic].
+    const KIND: ModelKind;
+
+    /// The trained smartcore model, exactly as the guest reads it. Both are
+    /// declared with the aliases of `smartcore_ml_common`, and the
+    /// `Predictor` bound ties the model's label type to [Self::Output].
+    type Model: Serialize + DeserializeOwned + Predictor<DenseMatrix<f64>, Self::Output>;
+    /// The predictions the guest commits to the journal, see [Committed].
+    type Output: Serialize + DeserializeOwned + Debug + PartialEq;
+
+    /// The model reduced to its parameters for the fixed-point guest, see
+    /// [fixed]. Only linear regressions and tree ensembles have one.
+    fn plain_model(_model: &Self::Model) -> Result<PlainModel<f64>> {
+        Err(Error::FixedPoint(format!(
+            "{} has no fixed-point counterpart",
+            Self::NAME
+        )))
+    }
+}
+
+/// The journal of the guest of `M`: its predictions next to the model and
+/// input data, each either verbatim or as a digest. Journals are decoded
+/// strictly into this type, see [decode_journal].
+pub type Committed<M> =
+    Prediction<<M as BenchModel>::Model, DenseMatrix<f64>, <M as BenchModel>::Output>;
+
+/// Which guest program runs a model's inference.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Guest {
+    /// The guest of the model alone, e.g. `methods/linear_regression`.
+    #[default]
+    Dedicated,
+    /// `methods/inference`, which runs every model by dispatching on its
+    /// [ModelKind]. Its journals are laid out like those of the dedicated
+    /// guests, preceded by the kind of the model.
+    Generic,
+}
+
+impl Guest {
+    pub fn elf<M: BenchModel>(self) -> &'static [u8] {
+        match self {
+            Guest::Dedicated => M::ELF,
+            Guest::Generic => INFERENCE_ELF,
+        }
+    }
+
+    pub fn image_id<M: BenchModel>(self) -> [u32; 8] {
+        match self {
+            Guest::Dedicated => M::IMAGE_ID,
+            Guest::Generic => INFERENCE_ID,
+        }
+    }
+
+    /// Decodes a journal of this guest run on the model of `M`. The image ID
+    /// of the generic guest does not say which model it ran, and journals of
+    /// linear regressions and te_regressions share a layout, so the kind it
+    /// commits must be [BenchModel::KIND].
+    pub fn decode_journal<M: BenchModel>(self, journal: &Journal) -> Result<Committed<M>> {
+        match self {
+            Guest::Dedicated => decode_journal(journal),
+            Guest::Generic => decode_tagged_journal::<M>(journal),
+        }
+    }
+}
+
+/// Decodes the journal of a guest that commits the kind of the model ahead
+/// of its predictions, which must be [BenchModel::KIND].
+fn decode_tagged_journal<M: BenchModel>(journal: &Journal) -> Result<Committed<M>> {
+    let (kind, prediction): (ModelKind, Committed<M>) = decode_journal(journal)?;
+    if kind != M::KIND {
+        return Err(Error::Journal(format!(
+            "journal is of a {:?} model, not of {}",
+            kind,
+            M::NAME
+        )));
+    }
+    Ok(prediction)
+}
+
+/// Runs the guest chosen by `opts.guest` in the executor. The generic guest
+/// is given the model tagged with its kind.
+fn execute_guest<M: BenchModel>(
+    model: &M::Model,
+    data: &DenseMatrix<f64>,
+    opts: &RunOptions,
+    profile: Option<&Path>,
+) -> Result<(SessionInfo, Vec<PhaseCycles>, Duration)> {
+    let elf = opts.guest.elf::<M>();
+    match opts.guest {
+        Guest::Dedicated => host::execute(model, data, elf, opts, profile),
+        Guest::Generic => host::execute(&(M::KIND, model), data, elf, opts, profile),
+    }
+}
+
+/// Proves the guest chosen by `opts.guest`, see [execute_guest].
+fn predict_guest<M: BenchModel>(
+    model: &M::Model,
+    data: &DenseMatrix<f64>,
+    opts: &RunOptions,
+) -> Result<(Receipt, Duration)> {
+    let elf = opts.guest.elf::<M>();
+    match opts.guest {
+        Guest::Dedicated => host::predict(model, data, elf, opts),
+        Guest::Generic => host::predict(&(M::KIND, model), data, elf, opts),
+    }
+}
+
+/// Proves `opts.warmup` + `opts.runs` times with `prove_once` and verifies
+/// the last receipt against `image_id`, returning the proving times, that
+/// receipt and its verification time, next to the peak memory of it all.
+/// `opts.runs` must be at least one.
+fn prove_runs(
+    opts: &RunOptions,
+    image_id: [u32; 8],
+    mut prove_once: impl FnMut() -> Result<(Receipt, Duration)>,
+) -> (Result<(Vec<Duration>, Receipt, Duration)>, Option<u64>) {
+    memory::measure(|| -> Result<_> {
+        if opts.runs == 0 {
+            return Err(Error::Prover(anyhow::anyhow!(
+                "at least one measured proof is needed"
+            )));
+        }
+        let mut proving_times = Vec::new();
+        let mut receipt = None;
+        for _ in 0..opts.warmup + opts.runs {
+            let (run_receipt, proving_time) = prove_once()?;
+            proving_times.push(proving_time);
+            receipt = Some(run_receipt);
+        }
+        let receipt = receipt.unwrap();
+        let verify_time = host::verify(&receipt, image_id)?;
+        Ok((proving_times, receipt, verify_time))
+    })
+}
+
+pub struct LinearRegressions;
+
+impl BenchModel for LinearRegressions {
+    const NAME: &'static str = "linear_regressions";
+    const ARTIFACT: &'static str = "linear_regression";
+    const ELF: &'static [u8] = LINEAR_REGRESSION_ELF;
+    const IMAGE_ID: [u32; 8] = LINEAR_REGRESSION_ID;
+    const KIND: ModelKind = ModelKind::LinearRegression;
+
+    type Model = LinearRegressionModel;
+    type Output = Vec<LinearRegressionLabel>;
+
+    fn plain_model(model: &Self::Model) -> Result<PlainModel<f64>> {
+        Ok(fixed::linear(model))
+    }
+}
+
+pub struct RandomForests;
+
+impl BenchModel for RandomForests {
+    const NAME: &'static str = "random_forests";
+    const ARTIFACT: &'static str = "random_forest";
+    const ELF: &'static [u8] = RANDOM_FOREST_ELF;
+    const IMAGE_ID: [u32; 8] = RANDOM_FOREST_ID;
+    const KIND: ModelKind = ModelKind::RandomForest;
+
+    type Model = RandomForestModel;
+    type Output = Vec<RandomForestLabel>;
+
+    fn plain_model(model: &Self::Model) -> Result<PlainModel<f64>> {
+        fixed::forest_classifier(model)
+    }
+}
+
+pub struct SvmClassifications;
+
+impl BenchModel for SvmClassifications {
+    const NAME: &'static str = "svm_classifications";
+    const ARTIFACT: &'static str = "svm_classification";
+    const ELF: &'static [u8] = SVM_CLASSIFICATION_ELF;
+    const IMAGE_ID: [u32; 8] = SVM_CLASSIFICATION_ID;
+    const KIND: ModelKind = ModelKind::SvmClassification;
+
+    type Model = SvmClassificationModel<'static>;
+    type Output = Vec<SvmClassificationLabel>;
+}
+
+pub struct TeRegressions;
+
+impl BenchModel for TeRegressions {
+    const NAME: &'static str = "te_regressions";
+    const ARTIFACT: &'static str = "te_regression";
+    const ELF: &'static [u8] = TE_REGRESSION_ELF;
+    const IMAGE_ID: [u32; 8] = TE_REGRESSION_ID;
+    const KIND: ModelKind = ModelKind::TeRegression;
+
+    type Model = TeRegressionModel;
+    type Output = Vec<TeRegressionLabel>;
+
+    fn plain_model(model: &Self::Model) -> Result<PlainModel<f64>> {
+        fixed::forest_regressor(model)
+    }
+}
+
+/// Every model the host can run, in the order they are benchmarked.
+pub const MODELS: &[&dyn DynBenchModel] = &[
+    &LinearRegressions,
+    &RandomForests,
+    &SvmClassifications,
+    &TeRegressions,
+];
+
+/// Looks up a registered model by its [BenchModel::NAME].
+pub fn find(name: &str) -> Result<&'static dyn DynBenchModel> {
+    MODELS
+        .iter()
+        .copied()
+        .find(|model| model.name() == name)
+        .ok_or_else(|| Error::UnknownModel(name.to_string()))
+}
+
+/// Object safe counterpart of [BenchModel], implemented for every
+/// [BenchModel] so that models can be stored in [MODELS] and driven by name.
+pub trait DynBenchModel: Sync {
+    fn name(&self) -> &'static str;
+
+    /// Runs the guest in the executor only, profiling it when
+    /// `opts.profile_dir` is set.
+    fn execute(&self, opts: &RunOptions) -> Result<RunReport>;
+
+    /// Runs the guest in the executor and then proves it `opts.warmup` +
+    /// `opts.runs` times, returning the receipt of the last proof once it
+    /// verifies against the image ID. With
+    /// `opts.succinct` that receipt is compressed into a succinct receipt.
+    fn prove(&self, opts: &RunOptions) -> Result<(RunReport, Receipt)>;
+
+    /// Runs the `raw_bytes` guest in the executor only, with the model and
+    /// input data sent as raw bytes in `format`, see [wire]. In rmp they are
+    /// the bytes of the artifacts.
+    fn execute_raw(&self, opts: &RunOptions, format: WireFormat) -> Result<RunReport>;
+
+    /// Runs the model in the `fixed_point` guest at `scale` fractional bits,
+    /// proving it like [DynBenchModel::prove] unless `execute_only`, and
+    /// reports how far its predictions are from the `f64` ones.
+    fn fixed_point(&self, opts: &RunOptions, scale: u32, execute_only: bool) -> Result<RunReport>;
+
+    /// Verifies `receipt` against the image ID of `guest`.
+    fn verify(&self, receipt: &Receipt, guest: Guest) -> Verification;
+
+    /// Decodes the journal of `receipt`, which is only to be trusted once
+    /// [DynBenchModel::verify] passed.
+    fn read_journal(&self, receipt: &Receipt, guest: Guest) -> Result<VerifiedJournal>;
+
+    /// Returns the digest of the model artifact at `path`, as the guest
+    /// commits it to the journal.
+    fn model_digest(&self, path: &Path) -> Result<Digest>;
+
+    fn inspect(&self, opts: &RunOptions) -> Result<Inspection>;
+}
+
+impl<M: BenchModel + Sync> DynBenchModel for M {
+    fn name(&self) -> &'static str {
+        M::NAME
+    }
+
+    fn execute(&self, opts: &RunOptions) -> Result<RunReport> {
+        let (model, data) = load_inputs::<M::Model>(opts, M::ARTIFACT)?;
+        let profile_path = opts.profile_path(M::NAME);
+        let (execution, execution_peak_rss_kb) =
+            memory::measure(|| execute_guest::<M>(&model, &data, opts, profile_path.as_deref()));
+        let (session, cycle_phases, execution_time) = execution?;
+        let journal = opts.guest.decode_journal::<M>(&session.journal)?;
+        let mut report = RunReport::new(M::NAME, &journal, &data, &session, execution_time);
+        report.segment_limit_po2 = opts.segment_limit_po2;
+        report.cycle_phases = cycle_phases;
+        report.record_guest(opts.guest, opts.guest.elf::<M>());
+        report.profile_path = profile_path;
+        report.execution_peak_rss_kb = execution_peak_rss_kb;
+        report.record_peak_rss();
+        Ok(report)
+    }
+
+    fn prove(&self, opts: &RunOptions) -> Result<(RunReport, Receipt)> {
+        let (model, data) = load_inputs::<M::Model>(opts, M::ARTIFACT)?;
+        // The prover does not expose cycle counts, so the guest is run in the
+        // executor first to collect them.
+        let (execution, execution_peak_rss_kb) =
+            memory::measure(|| execute_guest::<M>(&model, &data, opts, None));
+        let (session, cycle_phases, execution_time) = execution?;
+        let (proving, proving_peak_rss_kb) = prove_runs(opts, opts.guest.image_id::<M>(), || {
+            predict_guest::<M>(&model, &data, opts)
+        });
+        let (proving_times, mut receipt, verify_time) = proving?;
+        // We read the result that the guest code committed to the journal.
+        let journal = opts.guest.decode_journal::<M>(&receipt.journal)?;
+        let mut report = RunReport::new(M::NAME, &journal, &data, &session, execution_time);
+        report.segment_limit_po2 = opts.segment_limit_po2;
+        report.cycle_phases = cycle_phases;
+        report.record_guest(opts.guest, opts.guest.elf::<M>());
+        report.record_proofs(&proving_times, opts.warmup, &receipt, verify_time);
+        report.execution_peak_rss_kb = execution_peak_rss_kb;
+        report.proving_peak_rss_kb = proving_peak_rss_kb;
+        if opts.succinct {
+            let (compression, peak_rss_kb) = memory::measure(|| -> Result<_> {
+                let (succinct_receipt, compression_time) = host::compress(&receipt)?;
+                let verify_time = host::verify(&succinct_receipt, opts.guest.image_id::<M>())?;
+                Ok((succinct_receipt, compression_time, verify_time))
+            });
+            let (succinct_receipt, compression_time, succinct_verify_time) = compression?;
+            report.record_succinct(
+                compression_time,
+                &succinct_receipt,
+                succinct_verify_time,
+                peak_rss_kb,
+            );
+            receipt = succinct_receipt;
+        }
+        report.record_peak_rss();
+        Ok((report, receipt))
+    }
+
+    fn execute_raw(&self, opts: &RunOptions, format: WireFormat) -> Result<RunReport> {
+        let (model, data) = load_inputs::<M::Model>(opts, M::ARTIFACT)?;
+        let paths = opts.artifact_paths(M::ARTIFACT);
+        // The artifacts are stored as rmp, so the guest gets their bytes as
+        // they are. Input data from a batch file or synthetic rows has no
+        // artifact and is encoded like the other formats.
+        let data_from_artifact = opts.batch_file.is_none() && opts.synthetic_rows.is_none();
+        let model_bytes = match format {
+            WireFormat::Rmp => read_artifact_bytes(&paths.model)?,
+            _ => wire::encode(format, &model)?,
+        };
+        let data_bytes = match format {
+            WireFormat::Rmp if data_from_artifact => read_artifact_bytes(&paths.data)?,
+            _ => wire::encode(format, &data)?,
+        };
+        let (execution, execution_peak_rss_kb) = memory::measure(|| {
+            host::execute_bytes(
+                &(M::KIND, format),
+                &model_bytes,
+                &data_bytes,
+                RAW_BYTES_ELF,
+                opts,
+            )
+        });
+        let (session, cycle_phases, execution_time) = execution?;
+        let journal = decode_tagged_journal::<M>(&session.journal)?;
+        let mut report = RunReport::new(M::NAME, &journal, &data, &session, execution_time);
+        report.segment_limit_po2 = opts.segment_limit_po2;
+        report.cycle_phases = cycle_phases;
+        report.record_guest(Guest::Dedicated, RAW_BYTES_ELF);
+        report.wire_format = Some(WireFormatReport {
+            format,
+            model_size_bytes: model_bytes.len(),
+            input_size_bytes: data_bytes.len(),
+        });
+        report.execution_peak_rss_kb = execution_peak_rss_kb;
+        report.record_peak_rss();
+        Ok(report)
+    }
+
+    fn fixed_point(&self, opts: &RunOptions, scale: u32, execute_only: bool) -> Result<RunReport> {
+        let (model, data) = load_inputs::<M::Model>(opts, M::ARTIFACT)?;
+        let plain_model = M::plain_model(&model)?;
+        let fixed_model = fixed::quantize(&plain_model, scale)?;
+        let fixed_data = fixed::quantize_matrix(&data, scale)?;
+        // Fail here rather than in the guest when the predictions overflow.
+        fixed::predict_fixed(&fixed_model, &fixed_data)?;
+        let (execution, execution_peak_rss_kb) = memory::measure(|| {
+            host::execute(&fixed_model, &fixed_data, FIXED_POINT_ELF, opts, None)
+        });
+        let (session, cycle_phases, execution_time) = execution?;
+        let journal: fixed::Committed = decode_journal(&session.journal)?;
+        let mut report = RunReport::new(M::NAME, &journal, &data, &session, execution_time);
+        report.segment_limit_po2 = opts.segment_limit_po2;
+        report.cycle_phases = cycle_phases;
+        report.record_guest(Guest::Dedicated, FIXED_POINT_ELF);
+        report.record_fixed_point(scale, &journal.y_hat, &fixed::predict(&plain_model, &data)?);
+        report.execution_peak_rss_kb = execution_peak_rss_kb;
+        if !execute_only {
+            let (proving, proving_peak_rss_kb) = prove_runs(opts, FIXED_POINT_ID, || {
+                host::predict(&fixed_model, &fixed_data, FIXED_POINT_ELF, opts)
+            });
+            let (proving_times, receipt, verify_time) = proving?;
+            report.record_proofs(&proving_times, opts.warmup, &receipt, verify_time);
+            report.proving_peak_rss_kb = proving_peak_rss_kb;
+        }
+        report.record_peak_rss();
+        Ok(report)
+    }
+
+    fn verify(&self, receipt: &Receipt, guest: Guest) -> Verification {
+        Verification {
+            image_id: Digest::from(guest.image_id::<M>()),
+            result: receipt
+                .verify(guest.image_id::<M>())
+                .map_err(|err| err.to_string()),
+        }
+    }
+
+    fn read_journal(&self, receipt: &Receipt, guest: Guest) -> Result<VerifiedJournal> {
+        let journal = guest.decode_journal::<M>(&receipt.journal)?;
+        Ok(VerifiedJournal {
+            predictions: serde_json::to_value(&journal.y_hat).unwrap(),
+            visibility: Visibility {
+                model: journal.model.disclosure(),
+                input: journal.input.disclosure(),
+            },
+            model_digest: journal.model.digest(),
+            input_digest: journal.input.digest(),
+        })
+    }
+
+    fn model_digest(&self, path: &Path) -> Result<Digest> {
+        Ok(digest(&load_model::<M::Model>(path)?))
+    }
+
+    fn inspect(&self, opts: &RunOptions) -> Result<Inspection> {
+        let paths = opts.artifact_paths(M::ARTIFACT);
+        let (model, data) = load_inputs::<M::Model>(opts, M::ARTIFACT)?;
+        Ok(Inspection {
+            model: M::NAME,
+            model_type: std::any::type_name::<M::Model>(),
+            model_size_bytes: read_artifact_bytes(&paths.model)?.len(),
+            guest_input_size_bytes: risc0_zkvm::serde::to_vec(&model)
+                .map_err(|err| Error::Encode {
+                    format: "risc0 serde",
+                    reason: err.to_string(),
+                })?
+                .len()
+                * 4,
+            model_path: paths.model.display().to_string(),
+            data_path: opts
+                .batch_file
+                .as_ref()
+                .unwrap_or(&paths.data)
+                .display()
+                .to_string(),
+            input_shape: data.shape(),
+            model_digest: digest(&model),
+            elf_size_bytes: opts.guest.elf::<M>().len(),
+            image_id: Digest::from(opts.guest.image_id::<M>()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn prove<M: BenchModel>() -> M::Output {
+        let opts = RunOptions::default();
+        let (model, data) = load_inputs::<M::Model>(&opts, M::ARTIFACT).unwrap();
+        let (receipt, _) = host::predict(&model, &data, M::ELF, &opts).unwrap();
+        let journal: Committed<M> = decode_journal(&receipt.journal).unwrap();
+        // The guest binds its predictions to exactly what the host sent it.
+        assert_eq!(journal.model, Disclosed::Private(digest(&model)));
+        assert_eq!(journal.input, Disclosed::Private(digest(&data)));
+        journal.y_hat
+    }
+
+    #[test]
+    fn model_names_are_unique() {
+        for (i, model) in MODELS.iter().enumerate() {
+            assert!(
+                MODELS[i + 1..]
+                    .iter()
+                    .all(|other| other.name() != model.name()),
+                "{} is registered twice",
+                model.name()
+            );
+        }
+    }
+
+    #[test]
+    fn every_model_executes() {
+        // The executor does not prove, so this covers every guest in seconds.
+        for model in MODELS {
+            let report = model.execute(&RunOptions::default()).unwrap();
+            assert!(report.segments > 0, "{} produced no segments", model.name());
+            assert!(report.user_cycles > 0, "{} ran no cycles", model.name());
+            assert!(report.total_cycles >= report.user_cycles);
+            let phases: Vec<&str> = report
+                .cycle_phases
+                .iter()
+                .map(|phase| phase.phase.as_str())
+                .collect();
+            assert_eq!(
+                phases,
+                ["start", "read_model", "read_input", "predict", "commit"]
+            );
+            let phase_cycles: u64 = report.cycle_phases.iter().map(|phase| phase.cycles).sum();
+            assert!(phase_cycles <= report.user_cycles);
+        }
+    }
+
+    #[test]
+    fn execute_writes_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = RunOptions {
+            profile_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let report = LinearRegressions.execute(&opts).unwrap();
+        let profile_path = report.profile_path.unwrap();
+        assert_eq!(profile_path, dir.path().join("linear_regressions.pb"));
+        assert!(std::fs::metadata(profile_path).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn public_parts_are_committed_verbatim() {
+        let opts = RunOptions {
+            visibility: Visibility {
+                model: Disclosure::Public,
+                input: Disclosure::Private,
+            },
+            ..Default::default()
+        };
+        let (model, data) = load_inputs::<<LinearRegressions as BenchModel>::Model>(
+            &opts,
+            LinearRegressions::ARTIFACT,
+        )
+        .unwrap();
+        let (session, _, _) =
+            host::execute(&model, &data, LinearRegressions::ELF, &opts, None).unwrap();
+        let journal: Committed<LinearRegressions> = decode_journal(&session.journal).unwrap();
+        assert_eq!(journal.model.digest(), digest(&model));
+        assert!(matches!(journal.model, Disclosed::Public(_)));
+        assert_eq!(journal.input, Disclosed::Private(digest(&data)));
+    }
+
+    #[test]
+    fn generic_guest_commits_like_dedicated_guests() {
+        for model in MODELS {
+            let dedicated = model.execute(&RunOptions::default()).unwrap();
+            let generic = model
+                .execute(&RunOptions {
+                    guest: Guest::Generic,
+                    ..Default::default()
+                })
+                .unwrap();
+            assert_eq!(generic.journal, dedicated.journal, "{}", model.name());
+            assert_eq!(generic.model_digest, dedicated.model_digest);
+            assert_eq!(generic.input_digest, dedicated.input_digest);
+        }
+    }
+
+    #[test]
+    fn generic_journal_names_its_model() {
+        let opts = RunOptions {
+            guest: Guest::Generic,
+            ..Default::default()
+        };
+        let (model, data) = load_inputs::<<LinearRegressions as BenchModel>::Model>(
+            &opts,
+            LinearRegressions::ARTIFACT,
+        )
+        .unwrap();
+        let (session, _, _) =
+            execute_guest::<LinearRegressions>(&model, &data, &opts, None).unwrap();
+        assert!(Guest::Generic
+            .decode_journal::<LinearRegressions>(&session.journal)
+            .is_ok());
+        // Both commit `Vec<u32>`, only the kind tells them apart.
+        let err = Guest::Generic
+            .decode_journal::<TeRegressions>(&session.journal)
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Journal(_)));
+    }
+
+    /// Runs the fixed-point guest on the model of `M` and checks that it
+    /// predicts what the host computes from the same quantized model.
+    fn execute_fixed_point<M: BenchModel>() {
+        let opts = RunOptions::default();
+        let (model, data) = load_inputs::<M::Model>(&opts, M::ARTIFACT).unwrap();
+        let plain_model = M::plain_model(&model).unwrap();
+        let fixed_model = fixed::quantize(&plain_model, fixed::DEFAULT_SCALE).unwrap();
+        let fixed_data = fixed::quantize_matrix(&data, fixed::DEFAULT_SCALE).unwrap();
+        let (session, _, _) =
+            host::execute(&fixed_model, &fixed_data, FIXED_POINT_ELF, &opts, None).unwrap();
+        let journal: fixed::Committed = decode_journal(&session.journal).unwrap();
+        assert_eq!(
+            journal.y_hat,
+            fixed_model.predict(&fixed_data).unwrap(),
+            "{}",
+            M::NAME
+        );
+    }
+
+    #[test]
+    fn fixed_point_guest_predicts_like_the_host() {
+        execute_fixed_point::<LinearRegressions>();
+        execute_fixed_point::<RandomForests>();
+        execute_fixed_point::<TeRegressions>();
+
+        let opts = RunOptions::default();
+        let report = TeRegressions
+            .fixed_point(&opts, fixed::DEFAULT_SCALE, true)
+            .unwrap();
+        let fixed_point = report.fixed_point.unwrap();
+        assert_eq!(fixed_point.scale, fixed::DEFAULT_SCALE);
+        assert!(fixed_point.mean_abs_deviation <= fixed_point.max_abs_deviation);
+
+        let err = SvmClassifications
+            .fixed_point(&opts, fixed::DEFAULT_SCALE, true)
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::FixedPoint(_)));
+        assert_eq!(err.exit_code(), 17);
+    }
+
+    #[test]
+    fn zero_runs_are_rejected() {
+        let opts = RunOptions {
+            runs: 0,
+            ..Default::default()
+        };
+        let (proving, _) = prove_runs(&opts, LinearRegressions::IMAGE_ID, || {
+            unreachable!("no proof is made")
+        });
+        assert!(matches!(proving, Err(Error::Prover(_))));
+    }
+
+    #[test]
+    fn unknown_model_is_reported() {
+        let err = find("decision_trees").err().unwrap();
+        assert!(matches!(err, Error::UnknownModel(_)));
+        assert_eq!(err.exit_code(), 3);
+    }
+
+    #[test]
+    fn linear_regression() {
+        assert_eq!(prove::<LinearRegressions>(), vec![3]);
+    }
+
+    #[test]
+    fn random_forest() {
+        assert_eq!(prove::<RandomForests>(), vec![0]);
+    }
+
+    #[test]
+    fn svm_classification() {
+        assert_eq!(
+            prove::<SvmClassifications>(),
+            vec![-1, -1, -1, -1, -1, -1, -1, -1, -1, -1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]
+        );
+    }
+
+    #[test]
+    fn te_regression() {
+        assert_eq!(prove::<TeRegressions>(), vec![11]);
+    }
+
+    #[test]
+    #[cfg(feature = "prove")]
+    fn succinct_receipt_verifies() {
+        let opts = RunOptions {
+            succinct: true,
+            ..Default::default()
+        };
+        let (report, receipt) = LinearRegressions.prove(&opts).unwrap();
+        let succinct = report.succinct.unwrap();
+        assert!(succinct.receipt_size_bytes < report.receipt_size_bytes.unwrap());
+        assert!(LinearRegressions
+            .verify(&receipt, Guest::Dedicated)
+            .result
+            .is_ok());
+    }
+}
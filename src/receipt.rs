@@ -0,0 +1,87 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use risc0_zkvm::{sha::Digest, Receipt};
+use serde::{Deserialize, Serialize};
+use smartcore_ml_common::Visibility;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::models::Guest;
+
+/// A receipt as written to disk by `prove --receipt`.
+///
+/// The model name and guest are stored next to the receipt so that `verify`
+/// can pick the image ID to check against without being told which model and
+/// guest produced it. Neither is authenticated: the image ID checks the
+/// guest, and for the generic guest the model kind committed to the journal
+/// checks the model, see [Guest::decode_journal].
+#[derive(Serialize, Deserialize)]
+pub struct SavedReceipt {
+    pub model: String,
+    pub guest: Guest,
+    pub receipt: Receipt,
+}
+
+impl SavedReceipt {
+    pub fn new(model: &str, guest: Guest, receipt: Receipt) -> Self {
+        SavedReceipt {
+            model: model.to_string(),
+            guest,
+            receipt,
+        }
+    }
+
+    /// Serializes the receipt with bincode and writes it to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let bytes = bincode::serialize(self).map_err(|source| Error::ReceiptFormat {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        fs::write(path, bytes).map_err(|source| Error::ReceiptIo {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|source| Error::ReceiptIo {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        bincode::deserialize(&bytes).map_err(|source| Error::ReceiptFormat {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+/// The outcome of verifying a receipt against a model's image ID.
+pub struct Verification {
+    pub image_id: Digest,
+    pub result: Result<(), String>,
+}
+
+/// The journal of a verified receipt.
+pub struct VerifiedJournal {
+    /// The predictions, decoded as the model's output type.
+    pub predictions: serde_json::Value,
+    /// Which of the model and input data the journal reveals.
+    pub visibility: Visibility,
+    /// Digests of the model and input data the predictions were computed
+    /// from, whether they were revealed or not.
+    pub model_digest: Digest,
+    pub input_digest: Digest,
+}
@@ -0,0 +1,505 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use risc0_zkvm::{sha::Digest, Receipt, SessionInfo};
+use serde::{Deserialize, Serialize};
+use smartcore::linalg::basic::{arrays::Array, matrix::DenseMatrix};
+use smartcore_ml_common::{PhaseCycles, Prediction, Visibility, WireFormat};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::fixed;
+use crate::host;
+use crate::memory;
+use crate::models::Guest;
+
+/// How a run report is written to stdout.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Human readable lines, one per metric.
+    Text,
+    /// A single JSON document. Guest output is redirected to stderr so that
+    /// stdout can be parsed as-is.
+    Json,
+}
+
+impl ReportFormat {
+    pub fn from_arg(value: &str) -> Self {
+        match value {
+            "json" => ReportFormat::Json,
+            _ => ReportFormat::Text,
+        }
+    }
+}
+
+/// Everything measured during a single `execute` or `prove` run.
+#[derive(Serialize, Deserialize)]
+pub struct RunReport {
+    pub model: String,
+    /// Which of the model and input data the guest revealed in the journal.
+    pub visibility: Visibility,
+    /// Digests of the model and input data the guest committed its
+    /// predictions to, whether it revealed them or not.
+    pub model_digest: String,
+    pub input_digest: String,
+    pub journal_size_bytes: usize,
+    /// The guest program that ran, and the size of its ELF.
+    pub guest: Guest,
+    pub elf_size_bytes: usize,
+    /// The run was made with `RISC0_DEV_MODE` enabled, so its receipt is fake
+    /// and its proving metrics must not be published.
+    pub dev_mode: bool,
+    /// The prover ran in this process, see [host::in_process_prover].
+    /// Otherwise it ran in `r0vm`, whose memory the peaks below leave out.
+    pub in_process_prover: bool,
+    pub journal: serde_json::Value,
+    pub total_cycles: u64,
+    pub user_cycles: u64,
+    /// User cycles of each phase of the guest, from the checkpoints it
+    /// reported while running in the executor.
+    pub cycle_phases: Vec<PhaseCycles>,
+    pub segments: usize,
+    /// Size of the thread pool the prover and executor ran on.
+    pub threads: usize,
+    /// Segment size limit the guest was run with, `None` for the risc0
+    /// default.
+    pub segment_limit_po2: Option<u32>,
+    /// Number of input rows predicted in this run.
+    pub rows: usize,
+    /// Total cycles amortized over the input rows.
+    pub cycles_per_row: f64,
+    pub execution_time_secs: f64,
+    pub proving_time_secs: Option<f64>,
+    /// Proving time amortized over the input rows.
+    pub proving_time_per_row_secs: Option<f64>,
+    /// Statistics over repeated proofs, present when more than one proof was
+    /// made.
+    pub proving_time_stats: Option<TimingStats>,
+    pub receipt_size_bytes: Option<u64>,
+    /// Peak memory while running the executor. Per phase peaks are only
+    /// measured on Linux.
+    pub execution_peak_rss_kb: Option<u64>,
+    /// Peak memory while proving and verifying.
+    pub proving_peak_rss_kb: Option<u64>,
+    /// Time to verify the composite receipt against the image ID.
+    pub verify_time_secs: Option<f64>,
+    /// The succinct receipt, present when the run compressed its receipt.
+    pub succinct: Option<SuccinctReport>,
+    /// How far the predictions are off, present when the run was made by
+    /// the fixed-point guest.
+    pub fixed_point: Option<FixedPointReport>,
+    /// The format the model and input data were sent in, present when the
+    /// run was made by the raw-bytes guest.
+    pub wire_format: Option<WireFormatReport>,
+    /// Peak memory of the whole run.
+    pub peak_rss_kb: Option<u64>,
+    /// pprof profile of the guest, present when the run was profiled.
+    pub profile_path: Option<PathBuf>,
+}
+
+impl RunReport {
+    /// Builds a report from the executor session of `model` on `data`.
+    /// Proving metrics are left empty until [RunReport::record_proofs].
+    pub fn new<M: Serialize, I: Serialize, J: Serialize>(
+        model: &str,
+        journal: &Prediction<M, I, J>,
+        data: &DenseMatrix<f64>,
+        session: &SessionInfo,
+        execution_time: Duration,
+    ) -> Self {
+        // Segments are padded to a power of two, so the total cycle count is
+        // what the prover actually pays for.
+        let total_cycles = session.segments.iter().map(|s| 1u64 << s.po2).sum();
+        let rows = data.shape().0;
+        RunReport {
+            model: model.to_string(),
+            visibility: Visibility {
+                model: journal.model.disclosure(),
+                input: journal.input.disclosure(),
+            },
+            model_digest: journal.model.digest().to_string(),
+            input_digest: journal.input.digest().to_string(),
+            journal_size_bytes: session.journal.bytes.len(),
+            guest: Guest::Dedicated,
+            elf_size_bytes: 0,
+            dev_mode: host::dev_mode(),
+            in_process_prover: host::in_process_prover(),
+            journal: serde_json::to_value(&journal.y_hat).unwrap(),
+            total_cycles,
+            user_cycles: session.segments.iter().map(|s| s.cycles as u64).sum(),
+            cycle_phases: Vec::new(),
+            segments: session.segments.len(),
+            threads: rayon::current_num_threads(),
+            segment_limit_po2: None,
+            rows,
+            cycles_per_row: total_cycles as f64 / rows as f64,
+            execution_time_secs: execution_time.as_secs_f64(),
+            proving_time_secs: None,
+            proving_time_per_row_secs: None,
+            proving_time_stats: None,
+            receipt_size_bytes: None,
+            execution_peak_rss_kb: None,
+            proving_peak_rss_kb: None,
+            verify_time_secs: None,
+            succinct: None,
+            fixed_point: None,
+            wire_format: None,
+            peak_rss_kb: None,
+            profile_path: None,
+        }
+    }
+
+    /// Records the proving times of every proof made in the run, the first
+    /// `warmup` of which are left out of the statistics, and the size and
+    /// verification time of the final receipt. The proving time reported is
+    /// the median of the measured proofs.
+    pub fn record_proofs(
+        &mut self,
+        proving_times: &[Duration],
+        warmup: usize,
+        receipt: &Receipt,
+        verify_time: Duration,
+    ) {
+        let stats = TimingStats::new(proving_times, warmup);
+        self.proving_time_secs = Some(stats.median_secs);
+        self.proving_time_per_row_secs = Some(stats.median_secs / self.rows as f64);
+        if proving_times.len() > 1 {
+            self.proving_time_stats = Some(stats);
+        }
+        self.receipt_size_bytes = bincode::serialized_size(receipt).ok();
+        self.verify_time_secs = Some(verify_time.as_secs_f64());
+    }
+
+    pub fn record_guest(&mut self, guest: Guest, elf: &[u8]) {
+        self.guest = guest;
+        self.elf_size_bytes = elf.len();
+    }
+
+    /// Records the compression of the composite receipt into
+    /// `succinct_receipt`.
+    pub fn record_succinct(
+        &mut self,
+        compression_time: Duration,
+        succinct_receipt: &Receipt,
+        succinct_verify_time: Duration,
+        peak_rss_kb: Option<u64>,
+    ) {
+        self.succinct = Some(SuccinctReport {
+            compression_time_secs: compression_time.as_secs_f64(),
+            receipt_size_bytes: bincode::serialized_size(succinct_receipt).unwrap_or_default(),
+            verify_time_secs: succinct_verify_time.as_secs_f64(),
+            peak_rss_kb,
+        });
+    }
+
+    /// Records how far `predictions`, in fixed point at `scale`, are from
+    /// the `expected` predictions computed in `f64`.
+    pub fn record_fixed_point(&mut self, scale: u32, predictions: &[i64], expected: &[f64]) {
+        let deviations: Vec<f64> = predictions
+            .iter()
+            .zip(expected)
+            .map(|(&y, expected)| (fixed::from_fixed(y, scale) - expected).abs())
+            .collect();
+        self.fixed_point = Some(FixedPointReport {
+            scale,
+            max_abs_deviation: deviations.iter().copied().fold(0.0, f64::max),
+            mean_abs_deviation: deviations.iter().sum::<f64>() / deviations.len() as f64,
+        });
+    }
+
+    /// Records the peak memory of the whole run, which is the largest of the
+    /// phase peaks when they were measured.
+    pub fn record_peak_rss(&mut self) {
+        self.peak_rss_kb = [
+            self.execution_peak_rss_kb,
+            self.proving_peak_rss_kb,
+            self.succinct
+                .as_ref()
+                .and_then(|succinct| succinct.peak_rss_kb),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+        .or_else(memory::peak_rss_kb);
+    }
+
+    pub fn print(&self, format: ReportFormat) {
+        match format {
+            ReportFormat::Json => println!("{}", serde_json::to_string_pretty(self).unwrap()),
+            ReportFormat::Text => {
+                println!("Model: {}", self.model);
+                if self.dev_mode {
+                    println!("Dev mode: receipt is fake, proving metrics are not meaningful");
+                }
+                println!("Prediction recorded in journal is: {}", self.journal);
+                if let Some(fixed_point) = &self.fixed_point {
+                    println!(
+                        "Fixed point: {} fractional bits, deviation from f64 max {:.3e}, mean {:.3e}",
+                        fixed_point.scale,
+                        fixed_point.max_abs_deviation,
+                        fixed_point.mean_abs_deviation
+                    );
+                }
+                println!(
+                    "Model: {:?}, digest {}",
+                    self.visibility.model, self.model_digest
+                );
+                println!(
+                    "Input: {:?}, digest {}",
+                    self.visibility.input, self.input_digest
+                );
+                println!("Journal size: {} bytes", self.journal_size_bytes);
+                if let Some(wire_format) = &self.wire_format {
+                    println!(
+                        "Wire format: {:?}, model {} bytes, input {} bytes",
+                        wire_format.format,
+                        wire_format.model_size_bytes,
+                        wire_format.input_size_bytes
+                    );
+                }
+                println!(
+                    "Guest: {:?}, ELF size {} bytes",
+                    self.guest, self.elf_size_bytes
+                );
+                println!("Total cycles: {}", self.total_cycles);
+                println!("User cycles: {}", self.user_cycles);
+                for phase in &self.cycle_phases {
+                    println!(
+                        "  {}: {} ({:.1}%)",
+                        phase.phase,
+                        phase.cycles,
+                        100.0 * phase.cycles as f64 / self.user_cycles as f64
+                    );
+                }
+                println!("Segments: {}", self.segments);
+                println!("Threads: {}", self.threads);
+                if let Some(po2) = self.segment_limit_po2 {
+                    println!("Segment limit: 2^{}", po2);
+                }
+                println!("Rows: {}", self.rows);
+                println!("Cycles per row: {:.0}", self.cycles_per_row);
+                println!("Execution time: {:.3}s", self.execution_time_secs);
+                if let Some(proving_time) = self.proving_time_secs {
+                    println!("Proving time: {:.3}s", proving_time);
+                }
+                if let Some(proving_time) = self.proving_time_per_row_secs {
+                    println!("Proving time per row: {:.3}s", proving_time);
+                }
+                if let Some(stats) = &self.proving_time_stats {
+                    println!(
+                        "Proving time over {} runs ({} warmup): min {:.3}s, median {:.3}s, mean {:.3}s, p95 {:.3}s, stddev {:.3}s",
+                        stats.runs,
+                        stats.warmup,
+                        stats.min_secs,
+                        stats.median_secs,
+                        stats.mean_secs,
+                        stats.p95_secs,
+                        stats.stddev_secs
+                    );
+                    println!("Cold proving time: {:.3}s", stats.cold_secs);
+                }
+                if let Some(receipt_size) = self.receipt_size_bytes {
+                    println!("Receipt size: {} bytes", receipt_size);
+                }
+                if let Some(verify_time) = self.verify_time_secs {
+                    println!("Verification time: {:.3}s", verify_time);
+                }
+                if let Some(succinct) = &self.succinct {
+                    println!(
+                        "Succinct receipt size: {} bytes",
+                        succinct.receipt_size_bytes
+                    );
+                    println!("Compression time: {:.3}s", succinct.compression_time_secs);
+                    println!(
+                        "Succinct verification time: {:.3}s",
+                        succinct.verify_time_secs
+                    );
+                }
+                if !self.in_process_prover {
+                    println!("Peak memory leaves out the external r0vm process");
+                }
+                if let Some(peak_rss) = self.peak_rss_kb {
+                    println!("Peak memory: {}kb", peak_rss);
+                }
+                if let Some(peak_rss) = self.execution_peak_rss_kb {
+                    println!("Peak memory while executing: {}kb", peak_rss);
+                }
+                if let Some(peak_rss) = self.proving_peak_rss_kb {
+                    println!("Peak memory while proving: {}kb", peak_rss);
+                }
+                if let Some(peak_rss) = self.succinct.as_ref().and_then(|s| s.peak_rss_kb) {
+                    println!("Peak memory while compressing: {}kb", peak_rss);
+                }
+                if let Some(profile_path) = &self.profile_path {
+                    println!("Guest profile: {}", profile_path.display());
+                }
+            }
+        }
+    }
+}
+
+/// A composite receipt compressed into a succinct receipt by the recursion
+/// prover.
+#[derive(Serialize, Deserialize)]
+pub struct SuccinctReport {
+    /// Time spent compressing, on top of the proving time.
+    pub compression_time_secs: f64,
+    pub receipt_size_bytes: u64,
+    pub verify_time_secs: f64,
+    /// Peak memory while compressing and verifying.
+    pub peak_rss_kb: Option<u64>,
+}
+
+/// The predictions of the fixed-point guest compared with the same model
+/// computed in `f64`.
+#[derive(Serialize, Deserialize)]
+pub struct FixedPointReport {
+    /// Number of fractional bits of the fixed-point values.
+    pub scale: u32,
+    pub max_abs_deviation: f64,
+    pub mean_abs_deviation: f64,
+}
+
+/// The encoded model and input data the raw-bytes guest decoded.
+#[derive(Serialize, Deserialize)]
+pub struct WireFormatReport {
+    pub format: WireFormat,
+    pub model_size_bytes: usize,
+    pub input_size_bytes: usize,
+}
+
+/// Summary statistics of repeated timings.
+#[derive(Serialize, Deserialize)]
+pub struct TimingStats {
+    /// Number of measured timings.
+    pub runs: usize,
+    /// Number of timings left out as warmup.
+    pub warmup: usize,
+    /// The very first timing, which pays for cold caches and lazy setup.
+    pub cold_secs: f64,
+    pub min_secs: f64,
+    pub median_secs: f64,
+    pub mean_secs: f64,
+    pub p95_secs: f64,
+    /// Sample standard deviation.
+    pub stddev_secs: f64,
+}
+
+impl TimingStats {
+    /// Summarizes `timings` after leaving out the first `warmup` of them.
+    /// Without warmup the cold timing is also part of the statistics.
+    pub fn new(timings: &[Duration], warmup: usize) -> Self {
+        let mut measured: Vec<f64> = timings[warmup..]
+            .iter()
+            .map(Duration::as_secs_f64)
+            .collect();
+        measured.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let n = measured.len();
+        let mean = measured.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            measured.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+        TimingStats {
+            runs: n,
+            warmup,
+            cold_secs: timings[0].as_secs_f64(),
+            min_secs: measured[0],
+            median_secs: if n % 2 == 0 {
+                (measured[n / 2 - 1] + measured[n / 2]) / 2.0
+            } else {
+                measured[n / 2]
+            },
+            mean_secs: mean,
+            // Nearest rank percentile
+            p95_secs: measured[((0.95 * n as f64).ceil() as usize).max(1) - 1],
+            stddev_secs: variance.sqrt(),
+        }
+    }
+}
+
+/// Prints the reports of several runs. A single report is printed on its own,
+/// several are printed as a JSON array or as blank line separated text.
+pub fn print_all(reports: &[RunReport], format: ReportFormat) {
+    match (reports, format) {
+        ([report], _) => report.print(format),
+        (_, ReportFormat::Json) => println!("{}", serde_json::to_string_pretty(reports).unwrap()),
+        (_, ReportFormat::Text) => {
+            for (i, report) in reports.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                report.print(format);
+            }
+        }
+    }
+}
+
+/// A description of a model's artifacts and guest, printed by `inspect`.
+pub struct Inspection {
+    pub model: &'static str,
+    pub model_type: &'static str,
+    pub model_path: String,
+    pub model_size_bytes: usize,
+    /// Size of the model once encoded for the guest with risc0 serde.
+    pub guest_input_size_bytes: usize,
+    pub data_path: String,
+    pub input_shape: (usize, usize),
+    /// Digest of the model, as the guest commits it to the journal.
+    pub model_digest: Digest,
+    pub elf_size_bytes: usize,
+    pub image_id: Digest,
+}
+
+impl Inspection {
+    pub fn print(&self) {
+        println!("Model: {}", self.model);
+        println!("Model type: {}", self.model_type);
+        println!("Model artifact: {}", self.model_path);
+        println!(
+            "Model size: {} bytes (rmp), {} bytes (guest input)",
+            self.model_size_bytes, self.guest_input_size_bytes
+        );
+        println!("Input artifact: {}", self.data_path);
+        println!(
+            "Input shape: {} x {}",
+            self.input_shape.0, self.input_shape.1
+        );
+        println!("Model digest: {}", self.model_digest);
+        println!("Guest ELF size: {} bytes", self.elf_size_bytes);
+        println!("Image ID: {}", self.image_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timing_stats_leave_out_warmup() {
+        let timings: Vec<Duration> = [9.0, 1.0, 4.0, 2.0, 3.0]
+            .iter()
+            .map(|secs| Duration::from_secs_f64(*secs))
+            .collect();
+        let stats = TimingStats::new(&timings, 1);
+        assert_eq!(stats.runs, 4);
+        assert_eq!(stats.cold_secs, 9.0);
+        assert_eq!(stats.min_secs, 1.0);
+        assert_eq!(stats.median_secs, 2.5);
+        assert_eq!(stats.mean_secs, 2.5);
+        assert_eq!(stats.p95_secs, 4.0);
+        assert!((stats.stddev_secs - (5.0f64 / 3.0).sqrt()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sweeps over a host setting, such as the segment size or the number of
+//! prover threads.
+//!
+//! Every value of a sweep runs in a fresh process of this binary, so that
+//! peak memory and the prover's lazily initialised state are measured per
+//! value rather than accumulated over the sweep.
+
+use serde::Serialize;
+use std::process::{Command, Stdio};
+
+use crate::error::{Error, Result};
+use crate::host::SEGMENT_PO2_RANGE;
+use crate::report::{ReportFormat, RunReport};
+
+/// The run report for one value of a sweep.
+#[derive(Serialize)]
+pub struct SweepPoint {
+    pub value: u32,
+    pub report: RunReport,
+}
+
+/// Parses a comma separated list of values and inclusive `low-high` ranges,
+/// e.g. `14-16,20`.
+pub fn parse_values(arg: &str) -> std::result::Result<Vec<u32>, String> {
+    let mut values = Vec::new();
+    for part in arg.split(',').map(str::trim) {
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<u32>()
+                .map_err(|err| format!("invalid value {:?}: {}", value, err))
+        };
+        match part.split_once('-') {
+            Some((low, high)) => {
+                let (low, high) = (parse(low)?, parse(high)?);
+                if low > high {
+                    return Err(format!(
+                        "invalid range {:?}: {} is above {}",
+                        part, low, high
+                    ));
+                }
+                values.extend(low..=high)
+            }
+            None => values.push(parse(part)?),
+        }
+    }
+    Ok(values)
+}
+
+/// Parses a segment size limit, which must be one risc0 can prove, see
+/// [SEGMENT_PO2_RANGE].
+pub fn segment_po2(arg: &str) -> std::result::Result<u32, String> {
+    match arg.trim().parse::<u32>() {
+        Ok(po2) if SEGMENT_PO2_RANGE.contains(&po2) => Ok(po2),
+        _ => Err(format!(
+            "invalid segment size {:?}, expected a power of two from {} to {}",
+            arg,
+            SEGMENT_PO2_RANGE.start(),
+            SEGMENT_PO2_RANGE.end()
+        )),
+    }
+}
+
+/// Parses the segment size limits of a po2 sweep with [parse_values], each
+/// checked with [segment_po2].
+pub fn segment_po2s(arg: &str) -> std::result::Result<Vec<u32>, String> {
+    let values = parse_values(arg)?;
+    for po2 in &values {
+        segment_po2(&po2.to_string())?;
+    }
+    Ok(values)
+}
+
+/// Parses the input sizes of a rows sweep with [parse_values]. Every run
+/// needs at least one row.
+pub fn row_counts(arg: &str) -> std::result::Result<Vec<u32>, String> {
+    let counts = parse_values(arg)?;
+    if counts.contains(&0) {
+        return Err("every input size must be at least one row".to_string());
+    }
+    Ok(counts)
+}
+
+/// Parses a thread count. rayon reads zero as one thread per core, so it is
+/// rejected rather than reported as zero threads.
+pub fn thread_count(arg: &str) -> std::result::Result<u32, String> {
+    match arg.trim().parse::<u32>() {
+        Ok(threads) if threads > 0 => Ok(threads),
+        _ => Err(format!(
+            "invalid thread count {:?}, expected a positive number",
+            arg
+        )),
+    }
+}
+
+/// Parses the thread counts of a thread sweep. `auto` stands for the powers of
+/// two up to the available parallelism, followed by the available parallelism
+/// itself when it is not a power of two. Anything else is parsed with
+/// [parse_values], each checked with [thread_count].
+pub fn thread_counts(arg: &str) -> std::result::Result<Vec<u32>, String> {
+    if arg != "auto" {
+        let values = parse_values(arg)?;
+        for threads in &values {
+            thread_count(&threads.to_string())?;
+        }
+        return Ok(values);
+    }
+    let available = std::thread::available_parallelism()
+        .map_err(|err| err.to_string())?
+        .get() as u32;
+    let mut counts: Vec<u32> = (0..)
+        .map(|exp| 1 << exp)
+        .take_while(|&count| count < available)
+        .collect();
+    counts.push(available);
+    Ok(counts)
+}
+
+/// Runs this binary with `args` followed by `flag <value>` for every value,
+/// and collects the JSON run reports. `args` must select `--report json`.
+pub fn sweep(args: &[String], flag: &str, values: &[u32]) -> Result<Vec<SweepPoint>> {
+    let exe = std::env::current_exe().map_err(|err| Error::Sweep(err.to_string()))?;
+    values
+        .iter()
+        .map(|&value| {
+            eprintln!("Running with {} {}", flag, value);
+            let output = Command::new(&exe)
+                .args(args)
+                .arg(flag)
+                .arg(value.to_string())
+                .stderr(Stdio::inherit())
+                .output()
+                .map_err(|err| Error::Sweep(err.to_string()))?;
+            if !output.status.success() {
+                return Err(Error::Sweep(format!(
+                    "run with {} {} exited with code {:?}",
+                    flag,
+                    value,
+                    output.status.code()
+                )));
+            }
+            let report = serde_json::from_slice(&output.stdout)
+                .map_err(|err| Error::Sweep(format!("invalid run report: {}", err)))?;
+            Ok(SweepPoint { value, report })
+        })
+        .collect()
+}
+
+/// Prints a sweep as a JSON array or as a table with one row per model and
+/// value. The speedup column compares the proving time, or the execution
+/// time when nothing was proven, with that of the first value of the same
+/// model.
+pub fn print_sweep(name: &str, points: &[SweepPoint], format: ReportFormat) {
+    if format == ReportFormat::Json {
+        println!("{}", serde_json::to_string_pretty(points).unwrap());
+        return;
+    }
+    let secs = |secs: Option<f64>| secs.map_or("-".to_string(), |secs| format!("{:.3}s", secs));
+    let time = |report: &RunReport| {
+        report
+            .proving_time_secs
+            .unwrap_or(report.execution_time_secs)
+    };
+    println!(
+        "{:>20} {:>10} {:>10} {:>14} {:>14} {:>14} {:>10} {:>14}",
+        "model", name, "segments", "total cycles", "execution", "proving", "speedup", "peak memory"
+    );
+    for point in points {
+        let report = &point.report;
+        let first = points
+            .iter()
+            .find(|first| first.report.model == report.model)
+            .unwrap();
+        println!(
+            "{:>20} {:>10} {:>10} {:>14} {:>14} {:>14} {:>10} {:>14}",
+            report.model,
+            point.value,
+            report.segments,
+            report.total_cycles,
+            secs(Some(report.execution_time_secs)),
+            secs(report.proving_time_secs),
+            format!("{:.2}x", time(&first.report) / time(report)),
+            report
+                .peak_rss_kb
+                .map_or("-".to_string(), |kb| format!("{}kb", kb))
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn values_and_ranges() {
+        assert_eq!(parse_values("14-16,20").unwrap(), vec![14, 15, 16, 20]);
+        assert_eq!(parse_values("1, 2 ,4").unwrap(), vec![1, 2, 4]);
+        assert!(parse_values("x").is_err());
+        assert!(parse_values("20-16").is_err());
+        assert_eq!(row_counts("1,10").unwrap(), vec![1, 10]);
+        assert!(row_counts("0,10").is_err());
+        assert_eq!(segment_po2s("16-18").unwrap(), vec![16, 17, 18]);
+        assert!(segment_po2s("12,16").is_err());
+        assert!(segment_po2("25").is_err());
+    }
+
+    #[test]
+    fn auto_thread_counts_end_at_available_parallelism() {
+        let available = std::thread::available_parallelism().unwrap().get() as u32;
+        let counts = thread_counts("auto").unwrap();
+        assert_eq!(counts[0], 1);
+        assert_eq!(*counts.last().unwrap(), available);
+        assert!(counts.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(thread_counts("1,3").unwrap(), vec![1, 3]);
+        assert!(thread_counts("0,2").is_err());
+        assert!(thread_count("0").is_err());
+    }
+}
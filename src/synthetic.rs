@@ -0,0 +1,102 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Synthetic input data of any number of rows, for measuring how a model
+//! scales with the size of its input.
+
+use smartcore::linalg::basic::{arrays::Array, matrix::DenseMatrix};
+
+/// Generates `rows` rows with the columns of `template`. Every value is drawn
+/// uniformly from the range its column spans in `template`, so that the rows
+/// resemble real inputs, from a fixed seed so that runs are reproducible.
+///
+/// The shipped input data of most models is a single row, which spans no
+/// range at all, and copies of one row would take the same path through a
+/// tree every time. A column that holds a single value is therefore widened
+/// to half that value on either side, or to [-1, 1] around zero. The rows
+/// then differ, but no longer follow the distribution of the training data,
+/// which the artifacts do not record.
+pub fn synthetic_rows(template: &DenseMatrix<f64>, rows: usize) -> DenseMatrix<f64> {
+    let (template_rows, cols) = template.shape();
+    let ranges: Vec<(f64, f64)> = (0..cols)
+        .map(|col| {
+            (0..template_rows)
+                .map(|row| *template.get((row, col)))
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                    (min.min(value), max.max(value))
+                })
+        })
+        .map(|(min, max)| {
+            if min < max {
+                return (min, max);
+            }
+            let spread = if min == 0.0 { 1.0 } else { min.abs() / 2.0 };
+            (min - spread, max + spread)
+        })
+        .collect();
+    let mut rng = SplitMix64(0x5eed);
+    let data: Vec<Vec<f64>> = (0..rows)
+        .map(|_| {
+            ranges
+                .iter()
+                .map(|&(min, max)| min + (max - min) * rng.next_f64())
+                .collect()
+        })
+        .collect();
+    DenseMatrix::from_2d_vec(&data)
+}
+
+/// The SplitMix64 generator, which is plenty for spreading inputs and saves a
+/// dependency on `rand`.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rows_stay_within_the_template_ranges() {
+        let template = DenseMatrix::from_2d_vec(&vec![vec![1.0, -4.0, 0.0], vec![3.0, -4.0, 0.0]]);
+        let data = synthetic_rows(&template, 100);
+        assert_eq!(data.shape(), (100, 3));
+        for row in 0..100 {
+            let value = *data.get((row, 0));
+            assert!((1.0..3.0).contains(&value), "{}", value);
+            // Columns holding a single value are widened around it.
+            let value = *data.get((row, 1));
+            assert!((-6.0..-2.0).contains(&value), "{}", value);
+            let value = *data.get((row, 2));
+            assert!((-1.0..1.0).contains(&value), "{}", value);
+        }
+        assert_ne!(data.get((0, 1)), data.get((1, 1)));
+        // Runs are reproducible.
+        let again = synthetic_rows(&template, 100);
+        assert_eq!(again.get((42, 0)), data.get((42, 0)));
+    }
+}
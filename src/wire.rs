@@ -0,0 +1,134 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Wire formats the model and input data can reach a guest in. Besides the
+//! values the host writes through risc0's serde, the `raw_bytes` guest reads
+//! them as bytes in one of the [WireFormat]s and decodes them itself, which
+//! tells what each format costs in cycles.
+
+use serde::Serialize;
+use smartcore_ml_common::WireFormat;
+
+use crate::error::{Error, Result};
+use crate::host::RunOptions;
+use crate::models::DynBenchModel;
+use crate::report::{ReportFormat, RunReport};
+
+/// The phases of a guest run spent getting the model and input data into
+/// memory, see `smartcore_ml_common::checkpoint`.
+const INPUT_PHASES: [&str; 4] = ["read_model", "read_input", "decode_model", "decode_input"];
+
+pub fn encode<T: Serialize>(format: WireFormat, value: &T) -> Result<Vec<u8>> {
+    let encoded = match format {
+        WireFormat::Rmp => rmp_serde::to_vec(value).map_err(|err| err.to_string()),
+        WireFormat::Bincode => bincode::serialize(value).map_err(|err| err.to_string()),
+        WireFormat::Postcard => postcard::to_allocvec(value).map_err(|err| err.to_string()),
+    };
+    encoded.map_err(|reason| Error::Encode {
+        format: format.name(),
+        reason,
+    })
+}
+
+/// The run report of a model with its inputs in one format. `risc0` stands
+/// for the values written through risc0's serde, as every other guest reads
+/// them.
+#[derive(Serialize)]
+pub struct FormatPoint {
+    pub format: &'static str,
+    pub report: RunReport,
+}
+
+impl FormatPoint {
+    /// Cycles the guest spent reading and decoding the model and input data.
+    pub fn input_cycles(&self) -> u64 {
+        self.report
+            .cycle_phases
+            .iter()
+            .filter(|phase| INPUT_PHASES.contains(&phase.phase.as_str()))
+            .map(|phase| phase.cycles)
+            .sum()
+    }
+}
+
+/// Executes `model` with its inputs written through risc0's serde and then
+/// in every [WireFormat].
+pub fn compare_formats(model: &dyn DynBenchModel, opts: &RunOptions) -> Result<Vec<FormatPoint>> {
+    let mut points = vec![FormatPoint {
+        format: "risc0",
+        report: model.execute(opts)?,
+    }];
+    for format in WireFormat::ALL {
+        points.push(FormatPoint {
+            format: format.name(),
+            report: model.execute_raw(opts, format)?,
+        });
+    }
+    Ok(points)
+}
+
+/// Prints the points as a JSON array or as a table with one row per model and
+/// format.
+pub fn print_formats(points: &[FormatPoint], format: ReportFormat) {
+    if format == ReportFormat::Json {
+        println!("{}", serde_json::to_string_pretty(points).unwrap());
+        return;
+    }
+    let bytes = |bytes: Option<usize>| bytes.map_or("-".to_string(), |bytes| bytes.to_string());
+    println!(
+        "{:>20} {:>10} {:>12} {:>12} {:>14} {:>14} {:>14}",
+        "model",
+        "format",
+        "model bytes",
+        "input bytes",
+        "input cycles",
+        "user cycles",
+        "total cycles"
+    );
+    for point in points {
+        let report = &point.report;
+        let sizes = report.wire_format.as_ref();
+        println!(
+            "{:>20} {:>10} {:>12} {:>12} {:>14} {:>14} {:>14}",
+            report.model,
+            point.format,
+            bytes(sizes.map(|sizes| sizes.model_size_bytes)),
+            bytes(sizes.map(|sizes| sizes.input_size_bytes)),
+            point.input_cycles(),
+            report.user_cycles,
+            report.total_cycles
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::MODELS;
+
+    #[test]
+    fn every_format_commits_like_risc0_serde() {
+        for model in MODELS {
+            let points = compare_formats(*model, &RunOptions::default()).unwrap();
+            let risc0 = &points[0].report;
+            assert!(risc0.wire_format.is_none());
+            for point in &points[1..] {
+                assert_eq!(point.report.journal, risc0.journal, "{}", point.format);
+                assert_eq!(point.report.model_digest, risc0.model_digest);
+                assert_eq!(point.report.input_digest, risc0.input_digest);
+                assert!(point.input_cycles() > 0);
+            }
+        }
+    }
+}
@@ -10,12 +10,15 @@ mod benchmarking_tests {
     static ENV_SETUP: Once = Once::new();
     static BENCHMARK_FILE: Once = Once::new();
     use regex::Regex;
+    use zkml_benchmarks::models::MODELS;
 
     // Sure to run this once
 
     lazy_static! {
         static ref CARGO_TARGET_DIR: String =
             var("CARGO_TARGET_DIR").unwrap_or_else(|_| "./target".to_string());
+        // Models are benchmarked in registry order.
+        static ref TESTS: Vec<&'static str> = MODELS.iter().map(|model| model.name()).collect();
     }
 
     fn create_benchmark_json_file() {
@@ -25,9 +28,32 @@ mod benchmarking_tests {
                 .status()
                 .expect("failed to execute process");
             assert!(status.success());
+            // Record the machine and commit the session runs on, once per file
+            init_binary();
+            record_environment();
         });
     }
 
+    fn record_environment() {
+        let output = Command::new("target/release/zkml-benchmarks")
+            .arg("fingerprint")
+            .output()
+            .expect("failed to execute process");
+        assert!(output.status.success());
+        let fingerprint: Value =
+            serde_json::from_slice(&output.stdout).expect("invalid fingerprint");
+
+        let benchmarks_json = std::fs::read_to_string("./benchmarks.json").unwrap();
+        let mut benchmarks_json: serde_json::Value =
+            serde_json::from_str(&benchmarks_json).unwrap();
+        benchmarks_json["environment"] = fingerprint;
+        std::fs::write(
+            "./benchmarks.json",
+            serde_json::to_string_pretty(&benchmarks_json).unwrap(),
+        )
+        .unwrap();
+    }
+
     fn setup_py_env() {
         ENV_SETUP.call_once(|| {
             // supposes that you have a virtualenv called .env and have run the following
@@ -70,21 +96,19 @@ mod benchmarking_tests {
         COMPILE.call_once(|| {
             println!("using cargo target dir: {}", *CARGO_TARGET_DIR);
             setup_py_env();
-            // Run `cargo build --release` first to build the risc0 binary
+            // Run `cargo build --release` first to build the risc0 binary. The
+            // prover is linked in so that its memory is measured by the host
             let status = Command::new("cargo")
-                .args(["build", "--release"])
+                .args(["build", "--release", "--features", "prove"])
                 .status()
                 .expect("failed to execute process");
             assert!(status.success());
         });
     }
 
-    const TESTS: [&str; 4] = [
-        "linear_regressions",
-        "random_forests",
-        "svm_classifications",
-        "te_regressions",
-    ];
+    // CI runs `run_benchmarks_::tests_N` for every N covered by the `seq!`
+    // below, so growing the registry also means growing that range.
+    const _: () = assert!(MODELS.len() == 4);
 
     macro_rules! test_func {
         () => {
@@ -117,7 +141,7 @@ mod benchmarking_tests {
                             // only artifacts are generated in the risc0 notebook
                             run_notebooks("./notebooks", test);
                             // we need to run the risc0 zkVM VM on the host to get the proving time
-                            run_risc0_zk_vm(test, TIME_CMD);
+                            run_risc0_zk_vm(test);
                             run_cairo_vm(test, TIME_CMD);
                             ezkl_cli_prove(test, TIME_CMD);
                             // pretty print the benchmarks.json file
@@ -180,17 +204,10 @@ mod benchmarking_tests {
         assert!(status.success());
     }
 
-    fn run_risc0_zk_vm(test: &str, time_cmd: &str) {
-        // Wrap the risc0 binry run command in the gnu time command
-        let command = format!(
-            "{} -v target/release/zkml-benchmarks --model {}",
-            time_cmd, test
-        );
-
-        // Run the command using Bash, capturing both stdout and stderr
-        let output = Command::new("bash")
-            .arg("-c")
-            .arg(&command)
+    fn run_risc0_zk_vm(test: &str) {
+        // The host measures its own peak memory, so it is not wrapped in gnu time
+        let output = Command::new("target/release/zkml-benchmarks")
+            .args(["prove", "--model", test, "--report", "json"])
             .output()
             .expect("Failed to execute command");
 
@@ -201,26 +218,69 @@ mod benchmarking_tests {
         println!("stdout: {}", stdout);
         println!("stderr: {}", stderr);
 
-        // Use regex to extract the Proving time and Memory usage
-        let proving_time_re = Regex::new(r"Proving time: (\d+\.\d+)s").unwrap();
-        let memory_usage_re = Regex::new(r"Maximum resident set size \(kbytes\): (\d+)").unwrap();
+        // The host exits with a distinct code per failure and prints the reason
+        // on a line starting with "Error: "
+        if !output.status.success() {
+            let reason = stderr
+                .lines()
+                .find(|line| line.starts_with("Error: "))
+                .unwrap_or("no error reported");
+            panic!(
+                "risc0 host failed for {} with exit code {:?}: {}",
+                test,
+                output.status.code(),
+                reason
+            );
+        }
 
-        let proving_time_r0 = proving_time_re
-            .captures(&stdout)
-            .and_then(|caps| caps.get(1))
-            .map_or("".to_string(), |m| m.as_str().to_string() + "s");
+        // The host writes its run report to stdout as a single JSON document
+        let report: Value = serde_json::from_str(&stdout).expect("invalid risc0 run report");
+        // Proving times of fake dev mode receipts must never be published by
+        // accident, so they are only recorded when explicitly allowed
+        let dev_mode = report["dev_mode"].as_bool().unwrap_or(false);
+        if dev_mode && var("ALLOW_DEV_MODE").is_err() {
+            panic!(
+                "risc0 host ran {} with RISC0_DEV_MODE enabled; unset it or set ALLOW_DEV_MODE=1 to record fake receipts",
+                test
+            );
+        }
 
-        let memory_usage_r0 = memory_usage_re
-            .captures(&stderr)
-            .and_then(|caps| caps.get(1))
-            .map_or("".to_string(), |m| m.as_str().to_string() + "kb");
+        // The host only measures its own memory, which leaves out a prover
+        // run in the external r0vm process
+        if report["in_process_prover"].as_bool() != Some(true) {
+            panic!(
+                "risc0 host proved {} outside its process, so its peak memory is not measured; build it with --features prove and leave RISC0_PROVER unset",
+                test
+            );
+        }
+
+        let proving_time_r0 = report["proving_time_secs"]
+            .as_f64()
+            .expect("run report has no proving time");
+        let verify_time_r0 = report["verify_time_secs"]
+            .as_f64()
+            .expect("run report has no verification time");
+        let proof_size_r0 = report["receipt_size_bytes"]
+            .as_u64()
+            .expect("run report has no receipt size");
+
+        let memory_usage_r0 = report["peak_rss_kb"]
+            .as_u64()
+            .map_or("".to_string(), |kb| format!("{}kb", kb));
 
         update_benchmarks_json(
             test,
             "riscZero",
-            Value::String(proving_time_r0),
+            Value::String(format!("{}s", proving_time_r0)),
             Value::String(memory_usage_r0),
+            &[
+                ("verifyTime", json!(format!("{}s", verify_time_r0))),
+                ("proofSize", json!(format!("{}B", proof_size_r0))),
+            ],
         );
+        if dev_mode {
+            mark_dev_mode(test, "riscZero");
+        }
     }
 
     fn run_cairo_vm(test: &str, time_cmd: &str) {
@@ -274,6 +334,7 @@ mod benchmarking_tests {
             "orion",
             json!(format!("{:.3}s", proving_time)),
             Value::String(memory_usage_kb),
+            &[],
         );
     }
 
@@ -320,6 +381,7 @@ mod benchmarking_tests {
             "ezkl",
             Value::String(proof_time.to_string() + "s"),
             Value::String(memory_usage.to_string() + "kb"),
+            &[],
         );
 
         // Assert proof path exists at path notebooks/{test}/proof.json
@@ -327,7 +389,13 @@ mod benchmarking_tests {
         assert!(Path::new(&proof_path).exists());
     }
 
-    fn update_benchmarks_json(test: &str, framework: &str, time: Value, memory: Value) {
+    fn update_benchmarks_json(
+        test: &str,
+        framework: &str,
+        time: Value,
+        memory: Value,
+        extra_series: &[(&str, Value)],
+    ) {
         // Read in the benchmarks.json file
         let benchmarks_json = std::fs::read_to_string("./benchmarks.json").unwrap();
         let mut benchmarks_json: serde_json::Value =
@@ -344,6 +412,14 @@ mod benchmarking_tests {
             .unwrap();
         memory_usage_list.push(memory);
 
+        // Append framework specific series, such as verifyTime and proofSize
+        for (series, value) in extra_series {
+            benchmarks_json[test][framework][*series]
+                .as_array_mut()
+                .unwrap()
+                .push(value.clone());
+        }
+
         // Write to benchmarks.json file
         std::fs::write(
             "./benchmarks.json",
@@ -352,5 +428,20 @@ mod benchmarking_tests {
         .unwrap();
     }
 
+    fn mark_dev_mode(test: &str, framework: &str) {
+        let benchmarks_json = std::fs::read_to_string("./benchmarks.json").unwrap();
+        let mut benchmarks_json: serde_json::Value =
+            serde_json::from_str(&benchmarks_json).unwrap();
+
+        // Flag the entry so that its numbers are not mistaken for real proofs
+        benchmarks_json[test][framework]["dev_mode"] = Value::Bool(true);
+
+        std::fs::write(
+            "./benchmarks.json",
+            serde_json::to_string_pretty(&benchmarks_json).unwrap(),
+        )
+        .unwrap();
+    }
+
     test_func!();
 }